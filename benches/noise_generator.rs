@@ -0,0 +1,31 @@
+#[macro_use]
+extern crate criterion;
+extern crate auto_dungeon;
+
+use criterion::{black_box, Criterion};
+
+use auto_dungeon::Coordinates;
+use auto_dungeon::area::Area;
+use auto_dungeon::generator::{GeneratorParameters, NoiseGenerator};
+use auto_dungeon::noise_graph::NoiseGraph;
+
+/// Evaluation of every channel over the area displayed by the viewer, one hexagon at a time and in one batch
+fn sample_area(c: &mut Criterion) {
+    let noise_graph = NoiseGraph::load("config/noise.ron").expect("Could not load the noise graph");
+    let parameters = GeneratorParameters { seed: 0, humidity_scale: 0.97, humidity_bias: 0.1, world_scale: 16. };
+    let noise_generator = NoiseGenerator::new(&noise_graph, &parameters).expect("Could not build the noise generator");
+    let area = Area::hexagon(Coordinates { q: 0, r: 0 }, 25);
+
+    let mut group = c.benchmark_group("radius 25 area");
+    group.bench_function("per tile", |b| b.iter(|| black_box(&area).iter()
+        .map(|coordinates| {
+            let height = noise_generator.height(coordinates);
+            (height, noise_generator.humidity(coordinates), noise_generator.temperature(coordinates, height))
+        })
+        .collect::<Vec<(f64, f64, f64)>>()));
+    group.bench_function("batch", |b| b.iter(|| noise_generator.sample_area(black_box(area.coordinates()))));
+    group.finish();
+}
+
+criterion_group!(benches, sample_area);
+criterion_main!(benches);
@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+use std::iter::FromIterator;
+use std::slice::Iter;
+use std::vec::IntoIter;
+
+use tiles::Coordinates;
+
+/// Set of hexagons, iterated in the order they were added.
+///
+/// Shapes are built in rows of increasing r and q, except rings and spirals which go around their center.
+#[derive(Clone, Debug, Default)]
+pub struct Area {
+    coordinates: Vec<Coordinates>,
+    members: HashSet<Coordinates>,
+}
+
+impl Area {
+    /// Hexagons at most `radius` hexagons away from the center
+    pub fn hexagon(center: Coordinates, radius: i32) -> Area {
+        (-radius..=radius)
+            .flat_map(|r| ((-radius).max(-r - radius)..=radius.min(radius - r)).map(move |q| (q, r)))
+            .map(|(q, r)| center.shift(q, r))
+            .collect()
+    }
+
+    /// Hexagons exactly `radius` hexagons away from the center, going around it from the bottom left corner
+    pub fn ring(center: Coordinates, radius: i32) -> Area {
+        center.ring(radius).collect()
+    }
+
+    /// Same hexagons as `hexagon`, ordered from the center outwards ring by ring
+    pub fn spiral(center: Coordinates, radius: i32) -> Area {
+        center.spiral(radius).collect()
+    }
+
+    /// Hexagons drawn as a rectangle on screen, `width` hexagons per row and `height` rows from the top left corner.
+    /// Every other row is shifted by half a hexagon to the right.
+    pub fn rectangle(corner: Coordinates, width: i32, height: i32) -> Area {
+        (0..height)
+            .flat_map(|r| (0..width).map(move |q| (q - r.div_euclid(2), r)))
+            .map(|(q, r)| corner.shift(q, r))
+            .collect()
+    }
+
+    /// Hexagons from the corner to `width` - 1 along q and `height` - 1 along r
+    pub fn parallelogram(corner: Coordinates, width: i32, height: i32) -> Area {
+        (0..height)
+            .flat_map(|r| (0..width).map(move |q| (q, r)))
+            .map(|(q, r)| corner.shift(q, r))
+            .collect()
+    }
+
+    pub fn contains(&self, coordinates: &Coordinates) -> bool {
+        self.members.contains(coordinates)
+    }
+
+    pub fn len(&self) -> usize {
+        self.coordinates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coordinates.is_empty()
+    }
+
+    pub fn iter(&self) -> Iter<'_, Coordinates> {
+        self.coordinates.iter()
+    }
+
+    pub fn coordinates(&self) -> &[Coordinates] {
+        &self.coordinates
+    }
+
+    /// Minimum and maximum q and r of the area, as the opposite corners of a parallelogram
+    pub fn bounding_box(&self) -> Option<(Coordinates, Coordinates)> {
+        let first = *self.coordinates.first()?;
+        Some(self.iter().fold((first, first), |(min, max), coordinates| (
+            Coordinates { q: min.q.min(coordinates.q), r: min.r.min(coordinates.r) },
+            Coordinates { q: max.q.max(coordinates.q), r: max.r.max(coordinates.r) },
+        )))
+    }
+
+    /// Hexagons of both areas, those of this area first
+    pub fn union(&self, other: &Area) -> Area {
+        self.iter().chain(other.iter()).cloned().collect()
+    }
+
+    pub fn intersection(&self, other: &Area) -> Area {
+        self.iter().filter(|coordinates| other.contains(coordinates)).cloned().collect()
+    }
+
+    pub fn difference(&self, other: &Area) -> Area {
+        self.iter().filter(|coordinates| !other.contains(coordinates)).cloned().collect()
+    }
+
+    /// Area grown by `margin` hexagons in every direction
+    pub fn expand(&self, margin: i32) -> Area {
+        let mut expanded = self.clone();
+        let mut frontier = self.coordinates.clone();
+        for _ in 0..margin {
+            frontier = frontier.iter()
+                .flat_map(|coordinates| coordinates.neighbors())
+                .filter(|neighbor| expanded.insert(*neighbor))
+                .collect();
+        }
+        expanded
+    }
+
+    /// Adds the hexagon at the end of the area, returns false if it was already there
+    fn insert(&mut self, coordinates: Coordinates) -> bool {
+        let inserted = self.members.insert(coordinates);
+        if inserted {
+            self.coordinates.push(coordinates);
+        }
+        inserted
+    }
+}
+
+impl FromIterator<Coordinates> for Area {
+    /// Area of the hexagons in their first occurrence order, duplicates are skipped
+    fn from_iter<I: IntoIterator<Item=Coordinates>>(iter: I) -> Area {
+        let mut area = Area::default();
+        for coordinates in iter {
+            area.insert(coordinates);
+        }
+        area
+    }
+}
+
+impl IntoIterator for Area {
+    type Item = Coordinates;
+    type IntoIter = IntoIter<Coordinates>;
+
+    fn into_iter(self) -> IntoIter<Coordinates> {
+        self.coordinates.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Area {
+    type Item = &'a Coordinates;
+    type IntoIter = Iter<'a, Coordinates>;
+
+    fn into_iter(self) -> Iter<'a, Coordinates> {
+        self.coordinates.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use area::Area;
+    use tiles::Coordinates;
+
+    const CENTER: Coordinates = Coordinates { q: 3, r: -2 };
+
+    fn area(coordinates: &[(i32, i32)]) -> Area {
+        coordinates.iter().map(|(q, r)| Coordinates { q: *q, r: *r }).collect()
+    }
+
+    #[test]
+    fn rectangle_rows_line_up_on_screen() {
+        let (corner_x, corner_y) = CENTER.world_position();
+        let rectangle = Area::rectangle(CENTER, 5, 4);
+        assert_eq!(rectangle.len(), 20);
+        for (index, coordinates) in rectangle.iter().enumerate() {
+            let (row, column) = (index as i32 / 5, index as i32 % 5);
+            let (x, y) = coordinates.world_position();
+            // Odd rows are shifted by half a hexagon to the right
+            let expected_x = column as f64 + (row % 2) as f64 / 2.;
+            let expected_y = row as f64 * 3f64.sqrt() / 2.;
+            assert!((x - corner_x - expected_x).abs() < 1e-9, "{:?} at x {}", coordinates, x - corner_x);
+            assert!((y - corner_y - expected_y).abs() < 1e-9, "{:?} at y {}", coordinates, y - corner_y);
+        }
+    }
+
+    #[test]
+    fn expanding_a_hexagon_adds_its_rings() {
+        let single = Area::hexagon(CENTER, 0);
+        assert_eq!(single.len(), 1);
+        let expanded = single.expand(1);
+        assert_eq!(expanded.len(), 7);
+        assert!(Area::hexagon(CENTER, 1).iter().all(|coordinates| expanded.contains(coordinates)));
+        assert_eq!(single.expand(2).len(), 19);
+        assert_eq!(expanded.coordinates()[0], CENTER);
+    }
+
+    #[test]
+    fn set_operations_keep_the_first_order_without_duplicates() {
+        let first = area(&[(0, 0), (1, 0), (2, 0)]);
+        let second = area(&[(2, 0), (3, 0), (0, 0)]);
+        assert_eq!(first.union(&second).coordinates(), area(&[(0, 0), (1, 0), (2, 0), (3, 0)]).coordinates());
+        assert_eq!(first.intersection(&second).coordinates(), area(&[(0, 0), (2, 0)]).coordinates());
+        assert_eq!(second.intersection(&first).coordinates(), area(&[(2, 0), (0, 0)]).coordinates());
+        assert_eq!(first.difference(&second).coordinates(), area(&[(1, 0)]).coordinates());
+        assert_eq!(area(&[(1, 0), (0, 0), (1, 0)]).coordinates(), area(&[(1, 0), (0, 0)]).coordinates());
+    }
+
+    #[test]
+    fn bounding_box_spans_the_area() {
+        let bounds = Area::hexagon(CENTER, 2).bounding_box();
+        assert_eq!(bounds, Some((CENTER.shift(-2, -2), CENTER.shift(2, 2))));
+        assert_eq!(Area::default().bounding_box(), None);
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::fs;
+
+use ron;
+use serde::Deserialize;
+
+use terrain::BiomeType;
+
+/// Values sampled for a hexagon, on which the biome rules are evaluated
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Deserialize)]
+pub enum Channel {
+    Height,
+    Humidity,
+    Temperature,
+    WaterDistance,
+}
+
+impl Channel {
+    const ALL: [Channel; 4] = [Channel::Height, Channel::Humidity, Channel::Temperature, Channel::WaterDistance];
+}
+
+pub struct TileClimate {
+    pub height: f64,
+    pub humidity: f64,
+    pub temperature: f64,
+    pub water_distance: u32,
+}
+
+impl TileClimate {
+    fn channel(&self, channel: Channel) -> f64 {
+        match channel {
+            Channel::Height => self.height,
+            Channel::Humidity => self.humidity,
+            Channel::Temperature => self.temperature,
+            Channel::WaterDistance => self.water_distance as f64,
+        }
+    }
+}
+
+/// Half open range of values, the minimum is included and the maximum excluded
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Range {
+    #[serde(default)]
+    min: Option<f64>,
+    #[serde(default)]
+    max: Option<f64>,
+}
+
+impl Range {
+    const UNBOUNDED: Range = Range { min: None, max: None };
+
+    fn contains(&self, value: f64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value < max)
+    }
+
+    fn lower(&self) -> f64 {
+        self.min.unwrap_or(f64::NEG_INFINITY)
+    }
+
+    fn upper(&self) -> f64 {
+        self.max.unwrap_or(f64::INFINITY)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lower() >= self.upper()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BiomeRule {
+    biome: BiomeType,
+    /// Ranges the channels must be in, missing channels are unbounded
+    when: HashMap<Channel, Range>,
+}
+
+impl BiomeRule {
+    fn matches(&self, climate: &TileClimate) -> bool {
+        self.when.iter().all(|(channel, range)| range.contains(climate.channel(*channel)))
+    }
+
+    fn bounds(&self) -> Bounds {
+        Channel::ALL.map(|channel| *self.when.get(&channel).unwrap_or(&Range::UNBOUNDED))
+    }
+}
+
+/// Region covered by a rule, one range per channel in the order of `Channel::ALL`
+type Bounds = [Range; 4];
+
+/// Biome classification, loaded from a rules file.
+///
+/// Overrides are evaluated first, in order, and take precedence over everything else (water, beaches...).
+/// The biomes then form a table that must not overlap, typically bands of temperature split by humidity.
+/// Hexagons matched by no rule fall back to the default biome.
+#[derive(Debug, Deserialize)]
+pub struct BiomeRules {
+    overrides: Vec<BiomeRule>,
+    biomes: Vec<BiomeRule>,
+    default: BiomeType,
+}
+
+impl BiomeRules {
+    pub fn load(path: &str) -> Result<BiomeRules, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read biome rules {}: {}", path, e))?;
+        let rules: BiomeRules = ron::from_str(&content)
+            .map_err(|e| format!("Could not parse biome rules {}: {}", path, e))?;
+        rules.validate()
+            .map_err(|e| format!("Invalid biome rules {}: {}", path, e))?;
+        Ok(rules)
+    }
+
+    pub fn classify(&self, climate: &TileClimate) -> BiomeType {
+        self.overrides.iter()
+            .chain(self.biomes.iter())
+            .find(|rule| rule.matches(climate))
+            .map_or(self.default.clone(), |rule| rule.biome.clone())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let rules: Vec<(String, &BiomeRule)> = self.overrides.iter()
+            .enumerate()
+            .map(|(index, rule)| (format!("override #{} ({:?})", index + 1, rule.biome), rule))
+            .chain(self.biomes.iter()
+                .enumerate()
+                .map(|(index, rule)| (format!("biome #{} ({:?})", index + 1, rule.biome), rule)))
+            .collect();
+
+        for (name, rule) in rules.iter() {
+            if let Some((channel, _)) = rule.when.iter().find(|(_, range)| range.is_empty()) {
+                return Err(format!("{} has an empty {:?} range", name, channel));
+            }
+        }
+
+        for (first_index, first) in self.biomes.iter().enumerate() {
+            for (second_index, second) in self.biomes.iter().enumerate().skip(first_index + 1) {
+                if BiomeRules::intersect(&first.bounds(), &second.bounds()).is_some() {
+                    return Err(format!("biome #{} ({:?}) overlaps biome #{} ({:?})",
+                                       first_index + 1, first.biome, second_index + 1, second.biome));
+                }
+            }
+        }
+
+        // A rule is unreachable once the rules evaluated before it leave nothing of its region
+        for (index, (name, rule)) in rules.iter().enumerate() {
+            let remaining = rules.iter()
+                .take(index)
+                .fold(vec![rule.bounds()], |remaining, (_, previous)| remaining.iter()
+                    .flat_map(|bounds| BiomeRules::subtract(bounds, &previous.bounds()))
+                    .collect());
+            if remaining.is_empty() {
+                return Err(format!("{} is unreachable, previous rules already cover all of its values", name));
+            }
+        }
+        Ok(())
+    }
+
+    fn intersect(first: &Bounds, second: &Bounds) -> Option<Bounds> {
+        let mut intersection = *first;
+        for (range, other) in intersection.iter_mut().zip(second.iter()) {
+            let lower = range.lower().max(other.lower());
+            let upper = range.upper().min(other.upper());
+            if lower >= upper {
+                return None;
+            }
+            *range = Range { min: Some(lower).filter(|value| value.is_finite()), max: Some(upper).filter(|value| value.is_finite()) };
+        }
+        Some(intersection)
+    }
+
+    /// Splits what remains of `bounds` once `removed` is taken out of it into disjoint regions
+    fn subtract(bounds: &Bounds, removed: &Bounds) -> Vec<Bounds> {
+        let intersection = match BiomeRules::intersect(bounds, removed) {
+            None => return vec![*bounds],
+            Some(intersection) => intersection,
+        };
+
+        let mut remaining = Vec::new();
+        let mut current = *bounds;
+        for (index, range) in intersection.iter().enumerate() {
+            let below = Range { min: current[index].min, max: range.min };
+            if range.min.is_some() && !below.is_empty() {
+                let mut region = current;
+                region[index] = below;
+                remaining.push(region);
+            }
+            let above = Range { min: range.max, max: current[index].max };
+            if range.max.is_some() && !above.is_empty() {
+                let mut region = current;
+                region[index] = above;
+                remaining.push(region);
+            }
+            current[index] = *range;
+        }
+        remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ron;
+
+    use biomes::{BiomeRules, TileClimate};
+    use coastline::WaterDistanceMap;
+    use terrain::BiomeType;
+
+    fn validate(content: &str) -> Result<(), String> {
+        ron::from_str::<BiomeRules>(&format!("#![enable(implicit_some)] {}", content))
+            .expect("Could not parse the test rules")
+            .validate()
+    }
+
+    #[test]
+    fn configured_rules_are_valid() {
+        BiomeRules::load("config/biomes.ron").expect("Could not load the biome rules");
+    }
+
+    #[test]
+    fn rejects_overlapping_biomes() {
+        let result = validate("(
+            overrides: [],
+            biomes: [
+                (biome: Boreal, when: {Temperature: (max: 0.2)}),
+                (biome: Warm, when: {Temperature: (min: 0.1), Humidity: (max: 0.)}),
+            ],
+            default: Swamp,
+        )");
+        assert_eq!(result, Err("biome #1 (Boreal) overlaps biome #2 (Warm)".to_string()));
+    }
+
+    #[test]
+    fn rejects_shadowed_overrides() {
+        let result = validate("(
+            overrides: [
+                (biome: WShallow, when: {Height: (max: 0.)}),
+                (biome: WDeep, when: {Height: (max: -0.3)}),
+            ],
+            biomes: [],
+            default: Swamp,
+        )");
+        assert_eq!(result, Err("override #2 (WDeep) is unreachable, previous rules already cover all of its values".to_string()));
+    }
+
+    #[test]
+    fn rejects_empty_ranges() {
+        let result = validate("(
+            overrides: [],
+            biomes: [(biome: Desert, when: {Humidity: (min: 0.5, max: 0.5)})],
+            default: Swamp,
+        )");
+        assert_eq!(result, Err("biome #1 (Desert) has an empty Humidity range".to_string()));
+    }
+
+    #[test]
+    fn beaches_are_only_close_to_the_water() {
+        let biome_rules = BiomeRules::load("config/biomes.ron").expect("Could not load the biome rules");
+        let low_land = |water_distance| TileClimate { height: 0.02, humidity: 0.2, temperature: 0., water_distance };
+        assert_eq!(biome_rules.classify(&low_land(1)), BiomeType::Desert);
+        assert_eq!(biome_rules.classify(&low_land(2)), BiomeType::Desert);
+        assert_eq!(biome_rules.classify(&low_land(3)), BiomeType::Temperate);
+        assert_eq!(biome_rules.classify(&low_land(WaterDistanceMap::MAX_DISTANCE)), BiomeType::Temperate);
+    }
+}
@@ -0,0 +1,68 @@
+use std::mem;
+
+use area::Area;
+use tiles::{Coordinates, Hexagon};
+
+/// Coordinates of a chunk, chunk (0, 0) holds the hexagons with 0 <= q < SIZE and 0 <= r < SIZE
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ChunkCoordinates {
+    pub q: i32,
+    pub r: i32,
+}
+
+impl ChunkCoordinates {
+    pub fn of(coordinates: &Coordinates) -> ChunkCoordinates {
+        ChunkCoordinates { q: coordinates.q.div_euclid(Chunk::SIZE), r: coordinates.r.div_euclid(Chunk::SIZE) }
+    }
+
+    /// Every hexagon of the chunk
+    pub fn area(&self) -> Area {
+        Area::parallelogram(Coordinates { q: self.q * Chunk::SIZE, r: self.r * Chunk::SIZE }, Chunk::SIZE, Chunk::SIZE)
+    }
+}
+
+/// Parallelogram of SIZE x SIZE hexagons, hexagons not generated yet are missing
+pub struct Chunk {
+    hexagons: Vec<Option<Hexagon>>,
+    /// Tick of the grid when the chunk was last part of the area, the least recently used chunks are dropped first
+    pub last_used: u64,
+}
+
+impl Chunk {
+    pub const SIZE: i32 = 16;
+    /// Approximate memory used by a chunk, in bytes
+    pub const MEMORY: usize = (Chunk::SIZE * Chunk::SIZE) as usize * mem::size_of::<Option<Hexagon>>() + mem::size_of::<Chunk>();
+
+    pub fn get(&self, coordinates: &Coordinates) -> Option<&Hexagon> {
+        self.hexagons[Chunk::index(coordinates)].as_ref()
+    }
+
+    pub fn insert(&mut self, coordinates: &Coordinates, hexagon: Hexagon) {
+        self.hexagons[Chunk::index(coordinates)] = Some(hexagon);
+    }
+
+    /// Hexagons of the chunk at the given coordinates that are not generated yet
+    pub fn missing(&self, chunk_coordinates: &ChunkCoordinates) -> Area {
+        chunk_coordinates.area()
+            .into_iter()
+            .filter(|coordinates| self.get(coordinates).is_none())
+            .collect()
+    }
+
+    pub fn hexagons(&self, chunk_coordinates: &ChunkCoordinates) -> impl Iterator<Item=(Coordinates, &Hexagon)> {
+        chunk_coordinates.area()
+            .into_iter()
+            .zip(self.hexagons.iter())
+            .filter_map(|(coordinates, hexagon)| hexagon.as_ref().map(|hexagon| (coordinates, hexagon)))
+    }
+
+    fn index(coordinates: &Coordinates) -> usize {
+        (coordinates.r.rem_euclid(Chunk::SIZE) * Chunk::SIZE + coordinates.q.rem_euclid(Chunk::SIZE)) as usize
+    }
+}
+
+impl Default for Chunk {
+    fn default() -> Chunk {
+        Chunk { hexagons: (0..Chunk::SIZE * Chunk::SIZE).map(|_| None).collect(), last_used: 0 }
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::{HashMap, VecDeque};
+
+use tiles::Coordinates;
+
+/// Distance from hexagons to the closest water, i.e. hexagons below the sea level.
+///
+/// Distances are computed by a breadth first search over the given heights, which need to cover the area
+/// grown by `MAX_DISTANCE` hexagons for the distances of the area to be exact.
+pub struct WaterDistanceMap {
+    distances: HashMap<Coordinates, u32>,
+}
+
+impl WaterDistanceMap {
+    pub const MAX_DISTANCE: u32 = 8;
+
+    pub fn new(heights: &HashMap<Coordinates, f64>) -> WaterDistanceMap {
+        let mut distances: HashMap<Coordinates, u32> = HashMap::new();
+        let mut queue: VecDeque<Coordinates> = VecDeque::new();
+        heights.iter()
+            .filter(|(_, height)| **height < 0.)
+            .for_each(|(coordinates, _)| {
+                distances.insert(*coordinates, 0);
+                queue.push_back(*coordinates);
+            });
+
+        while let Some(coordinates) = queue.pop_front() {
+            let distance = distances[&coordinates] + 1;
+            if distance > WaterDistanceMap::MAX_DISTANCE {
+                continue;
+            }
+            for neighbor in coordinates.neighbors() {
+                if heights.contains_key(&neighbor) && !distances.contains_key(&neighbor) {
+                    distances.insert(neighbor, distance);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        WaterDistanceMap { distances }
+    }
+
+    /// Distance to the closest water, `MAX_DISTANCE` when there is none close enough
+    pub fn distance(&self, coordinates: &Coordinates) -> u32 {
+        *self.distances.get(coordinates).unwrap_or(&WaterDistanceMap::MAX_DISTANCE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use area::Area;
+    use coastline::WaterDistanceMap;
+    use tiles::Coordinates;
+
+    const ORIGIN: Coordinates = Coordinates { q: 0, r: 0 };
+
+    /// Land over the area, except the water hexagon
+    fn heights(area: &Area, water: &Coordinates) -> HashMap<Coordinates, f64> {
+        area.iter()
+            .map(|coordinates| (*coordinates, if coordinates == water { -0.5 } else { 0.5 }))
+            .collect()
+    }
+
+    #[test]
+    fn distances_grow_from_the_water_up_to_the_maximum() {
+        let area = Area::hexagon(ORIGIN, 12);
+        let water_distance_map = WaterDistanceMap::new(&heights(&area, &ORIGIN));
+        assert_eq!(water_distance_map.distance(&ORIGIN), 0);
+        for coordinates in area.iter() {
+            let expected = (coordinates.distance_to(&ORIGIN) as u32).min(WaterDistanceMap::MAX_DISTANCE);
+            assert_eq!(water_distance_map.distance(coordinates), expected, "{:?}", coordinates);
+        }
+    }
+
+    #[test]
+    fn distances_are_exact_at_the_edge_of_the_expanded_heights() {
+        let area = Area::hexagon(ORIGIN, 2);
+        let water = Coordinates { q: 7, r: 0 };
+        let water_distance_map = WaterDistanceMap::new(&heights(&area.expand(WaterDistanceMap::MAX_DISTANCE as i32), &water));
+        for coordinates in area.iter() {
+            let expected = (coordinates.distance_to(&water) as u32).min(WaterDistanceMap::MAX_DISTANCE);
+            assert_eq!(water_distance_map.distance(coordinates), expected, "{:?}", coordinates);
+        }
+        assert_eq!(water_distance_map.distance(&Coordinates { q: 2, r: 0 }), 5);
+
+        // Water outside of the given heights is not seen
+        let water_distance_map = WaterDistanceMap::new(&heights(&area, &water));
+        assert_eq!(water_distance_map.distance(&Coordinates { q: 2, r: 0 }), WaterDistanceMap::MAX_DISTANCE);
+    }
+}
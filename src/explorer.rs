@@ -0,0 +1,213 @@
+use std::error::Error;
+
+use area::Area;
+use biomes::BiomeRules;
+use generator::{GeneratorParameters, NoiseGenerator};
+use noise_graph::NoiseGraph;
+use tile_renderer::TileRenderer;
+use tiles::{Coordinates, Grid};
+use workers::ChunkWorkers;
+use world::World;
+
+/// Keys understood by the explorer, whatever the front end they come from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    Escape,
+    Char(char),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Quit,
+    /// Moves the center by the given (q, r) shift
+    Scroll(i32, i32),
+    ShiftHumidityBias(f64),
+    ShiftHumidityScale(f64),
+}
+
+impl Action {
+    pub fn from_key(key: Key) -> Option<Action> {
+        match key {
+            Key::Escape => Some(Action::Quit),
+            Key::Left => Some(Action::Scroll(-2, 0)),
+            Key::Right => Some(Action::Scroll(2, 0)),
+            Key::Up => Some(Action::Scroll(1, -2)),
+            Key::Down => Some(Action::Scroll(-1, 2)),
+            Key::Char(character) => match character.to_ascii_lowercase() {
+                'p' => Some(Action::ShiftHumidityBias(0.1)),
+                'm' => Some(Action::ShiftHumidityBias(-0.1)),
+                'o' => Some(Action::ShiftHumidityScale(0.01)),
+                'l' => Some(Action::ShiftHumidityScale(-0.01)),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Hexagonal area of the world around a moving center, regenerated when the parameters change.
+///
+/// Missing hexagons are generated on the calling thread, or in the background once workers are set.
+/// Once a sight is set, only what can be seen from the center is shown, along with what was seen before.
+pub struct Explorer<'a> {
+    noise_graph: &'a NoiseGraph,
+    biome_rules: &'a BiomeRules,
+    noise_generator: NoiseGenerator,
+    world: World,
+    /// Hexagons around the center, built again only when the center or the radius change
+    area: Area,
+    workers: Option<ChunkWorkers>,
+    /// Radius of the field of view of the center, if the fog of war is enabled
+    sight: Option<i32>,
+}
+
+impl<'a> Explorer<'a> {
+    /// Number of moves ahead of the center whose area is generated in the background, in the direction of the last move
+    const PREFETCH_MOVES: i32 = 8;
+
+    pub fn new(noise_graph: &'a NoiseGraph, biome_rules: &'a BiomeRules, parameters: GeneratorParameters,
+               center: Coordinates, radius: i32) -> Result<Explorer<'a>, Box<dyn Error>> {
+        let noise_generator = NoiseGenerator::new(noise_graph, &parameters)?;
+        let area = Area::hexagon(center, radius);
+        let grid = Grid::new(&noise_generator, biome_rules, &area)?;
+
+        Ok(Explorer { noise_graph, biome_rules, noise_generator, world: World { parameters, center, radius, grid }, area, workers: None, sight: None })
+    }
+
+    /// Explores a loaded world, hexagons leaving its area are generated from its parameters
+    pub fn from_world(noise_graph: &'a NoiseGraph, biome_rules: &'a BiomeRules, world: World) -> Result<Explorer<'a>, Box<dyn Error>> {
+        let noise_generator = NoiseGenerator::new(noise_graph, &world.parameters)?;
+        let area = Area::hexagon(world.center, world.radius);
+        Ok(Explorer { noise_graph, biome_rules, noise_generator, world, area, workers: None, sight: None })
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn area(&self) -> &Area {
+        &self.area
+    }
+
+    pub fn set_memory_budget(&mut self, memory_budget: usize) {
+        self.world.grid.set_memory_budget(memory_budget);
+    }
+
+    pub fn set_radius(&mut self, radius: i32) {
+        self.world.radius = radius;
+        self.update_area((0, 0));
+    }
+
+    pub fn set_workers(&mut self, workers: ChunkWorkers) {
+        self.workers = Some(workers);
+    }
+
+    /// Enables the fog of war, the center seeing up to `sight` hexagons away
+    pub fn set_sight(&mut self, sight: i32) {
+        self.sight = Some(sight);
+        self.world.grid.set_fog_of_war(true);
+        self.reveal();
+    }
+
+    /// Inserts the chunks generated in the background since the last call, returns whether any arrived
+    pub fn receive(&mut self) -> bool {
+        let result = match &mut self.workers {
+            Some(workers) => workers.receive(&mut self.world.grid),
+            None => Ok(false),
+        };
+        let received = match result {
+            Ok(received) => received,
+            Err(e) => {
+                self.stop_workers(&e);
+                true
+            }
+        };
+        if received {
+            // The new hexagons may be in sight, or unblock the view further
+            self.reveal();
+        }
+        received
+    }
+
+    pub fn apply(&mut self, action: Action) -> Result<(), Box<dyn Error>> {
+        match action {
+            Action::Quit => {}
+            Action::Scroll(q, r) => {
+                self.world.center = self.world.center.shift(q, r);
+                self.update_area((q, r));
+            }
+            Action::ShiftHumidityBias(shift) => {
+                self.world.parameters.humidity_bias += shift;
+                self.regenerate()?;
+            }
+            Action::ShiftHumidityScale(shift) => {
+                self.world.parameters.humidity_scale += shift;
+                self.regenerate()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn draw<R: TileRenderer>(&self, renderer: &mut R) {
+        self.world.grid.draw(renderer, self.world.center, &self.area);
+    }
+
+    /// Hexagon drawn at the given offset from the screen center, if it is generated
+    pub fn pick(&self, offset: (i32, i32)) -> Option<Coordinates> {
+        self.world.grid.pick(self.world.center, &self.area, offset)
+    }
+
+    /// Highlights the hexagon drawn at the given offset from the screen center, or nothing if there is none
+    pub fn select(&mut self, offset: (i32, i32)) -> Option<Coordinates> {
+        let selected = self.pick(offset);
+        self.world.grid.set_selected(selected);
+        selected
+    }
+
+    fn regenerate(&mut self) -> Result<(), Box<dyn Error>> {
+        self.noise_generator = NoiseGenerator::new(self.noise_graph, &self.world.parameters)?;
+        self.world.grid.clear();
+        if let Some(workers) = &mut self.workers {
+            workers.reset();
+        }
+        self.update_area((0, 0));
+        Ok(())
+    }
+
+    /// Generates the hexagons of the area around the center, or requests them with the area ahead to the workers
+    fn update_area(&mut self, (q, r): (i32, i32)) {
+        self.area = Area::hexagon(self.world.center, self.world.radius);
+        let requested = match &mut self.workers {
+            None => {
+                self.world.grid.at(&self.noise_generator, self.biome_rules, &self.area);
+                Ok(())
+            }
+            Some(workers) => {
+                self.world.grid.touch(&self.area);
+                let ahead = self.world.center.shift(q * Explorer::PREFETCH_MOVES, r * Explorer::PREFETCH_MOVES);
+                workers.request(&self.world.parameters, &self.world.grid, &self.area)
+                    .and(workers.request(&self.world.parameters, &self.world.grid, &Area::hexagon(ahead, self.world.radius)))
+            }
+        };
+        if let Err(e) = requested {
+            self.stop_workers(&e);
+        }
+        self.reveal();
+    }
+
+    /// Drops the failed workers, the hexagons are generated on the calling thread from then on
+    fn stop_workers(&mut self, error: &str) {
+        println!("Generating without the chunk workers: {}", error);
+        self.workers = None;
+        self.world.grid.at(&self.noise_generator, self.biome_rules, &self.area);
+    }
+
+    fn reveal(&mut self) {
+        if let Some(sight) = self.sight {
+            self.world.grid.reveal(&self.world.center, sight);
+        }
+    }
+}
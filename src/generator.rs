@@ -1,78 +1,167 @@
-use noise::{Curve, Fbm, MultiFractal, NoiseFn, Perlin, ScaleBias, Terrace};
+use std::f64::consts::PI;
 
-use divide::Divide;
+use noise::{NoiseFn, ScaleBias};
+use serde::{Deserialize, Serialize};
+
+use noise_graph::NoiseGraph;
+use shared_value::SharedValue;
 use tiles::Coordinates;
 
+/// Parameters of the world generation, on top of the noise graph
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GeneratorParameters {
+    pub seed: u32,
+    pub humidity_scale: f64,
+    pub humidity_bias: f64,
+    /// Number of hexagons spanned by one unit of noise, larger scales give wider continents
+    pub world_scale: f64,
+}
+
+/// Channels of a hexagon, evaluated together
+#[derive(Clone, Copy, Debug)]
+pub struct Sample {
+    pub height: f64,
+    pub humidity: f64,
+    pub temperature: f64,
+}
+
 pub struct NoiseGenerator {
     height_source_module: Box<dyn NoiseFn<f64, 2>>,
+    /// Reads the height of the point from `shared_height`, instead of evaluating the height modules again.
+    /// It must only be evaluated right after `shared_height` is set to the height of the same point.
     humidity_source_module: Box<dyn NoiseFn<f64, 2>>,
+    temperature_source_module: Box<dyn NoiseFn<f64, 2>>,
+    shared_height: SharedValue,
+    world_scale: f64,
 }
 
 impl NoiseGenerator {
-    const MAP_CENTER: Coordinates = Coordinates { q: 0, r: 0 };
+    /// Number of rows between the equator, at r = 0, and the poles
+    const POLE_DISTANCE: f64 = 150.;
+    /// Temperature lost per unit of height above the sea level
+    const LAPSE_RATE: f64 = 0.4;
 
-    pub fn new(seed: u32, humidity_scale: f64, humidity_bias: f64) -> NoiseGenerator {
-        println!("Generating new noise map with humidity scale {} and bias {}", humidity_scale, humidity_bias);
+    pub fn new(noise_graph: &NoiseGraph, parameters: &GeneratorParameters) -> Result<NoiseGenerator, String> {
+        if !parameters.world_scale.is_finite() || parameters.world_scale <= 0. {
+            return Err(format!("The world scale must be a positive number, got {}", parameters.world_scale));
+        }
+        println!("Generating new noise map with seed {}, humidity scale {} and bias {}",
+                 parameters.seed, parameters.humidity_scale, parameters.humidity_bias);
 
-        let scaled_height = NoiseGenerator::get_height_noise_function(seed);
+        let shared_height = SharedValue::new();
+        let scaled_humidity = ScaleBias::new(noise_graph.humidity_function(parameters.seed, &shared_height)?)
+            .set_scale(parameters.humidity_scale)
+            .set_bias(parameters.humidity_bias);
 
-        let base_humidity = Fbm::<Perlin>::new(seed + 1)
-            .set_frequency(0.10)
-            .set_persistence(0.5)
-            .set_lacunarity(2.208984375)
-            .set_octaves(2);
+        Ok(NoiseGenerator {
+            height_source_module: noise_graph.height_function(parameters.seed)?,
+            humidity_source_module: Box::new(scaled_humidity),
+            temperature_source_module: noise_graph.temperature_function(parameters.seed)?,
+            shared_height,
+            world_scale: parameters.world_scale,
+        })
+    }
 
-        let biased_height = ScaleBias::new(NoiseGenerator::get_height_noise_function(seed))
-            .set_bias(1.)
-            .set_scale(3.);
-        let multiply = Divide::new(base_humidity, biased_height);
-        let scaled_humidity = ScaleBias::new(multiply)
-            .set_scale(humidity_scale)
-            .set_bias(humidity_bias);
+    pub fn height(&self, coordinates: &Coordinates) -> f64 {
+        self.height_source_module.get(self.noise_point(coordinates))
+    }
 
-        NoiseGenerator {
-            height_source_module: scaled_height,
-            humidity_source_module: Box::new(scaled_humidity),
-        }
+    /// Humidity of a single hexagon, `sample_area` avoids evaluating the height again when it is known
+    pub fn humidity(&self, coordinates: &Coordinates) -> f64 {
+        self.shared_height.set(self.height(coordinates));
+        self.humidity_source_module.get(self.noise_point(coordinates))
     }
 
-    fn get_height_noise_function(seed: u32) -> Box<dyn NoiseFn<f64, 2>> {
-        let perlin: Perlin = Perlin::new(seed);
-
-        let height_terrace = Terrace::<f64, Perlin, 2>::new(perlin)
-            .add_control_point(-1.)
-            .add_control_point(-0.2)
-            .add_control_point(0.)
-            .add_control_point(0.4)
-            .add_control_point(0.8)
-            .add_control_point(1.2)
-            .add_control_point(2.);
-
-        let scaled_height = ScaleBias::new(height_terrace)
-            .set_scale(2.5)
-            .set_bias(0.5);
-        let curved_height = Curve::new(scaled_height)
-            .add_control_point(-3., -3.)
-            .add_control_point(-2.5, -2.)
-            .add_control_point(-2., -0.5)
-            .add_control_point(-1., -0.1)
-            .add_control_point(0., 0.2)
-            .add_control_point(1., 0.4)
-            .add_control_point(2., 1.5)
-            .add_control_point(2.5, 3.)
-            .add_control_point(3., 5.);
-        // TODO rivers
-        Box::new(curved_height)
+    /// Temperature, from 1 at the equator to -1 at the poles, cooling down with the height
+    pub fn temperature(&self, coordinates: &Coordinates, height: f64) -> f64 {
+        self.temperature_at(coordinates, self.noise_point(coordinates), height)
     }
 
-    pub fn height(&self, coordinates: &Coordinates) -> f64 {
-        let (x,y) = coordinates.as_offset(&NoiseGenerator::MAP_CENTER);
-        self.height_source_module.get([-x as f64 / 512., -y as f64 / 512.])
+    pub fn heights(&self, area: &[Coordinates]) -> Vec<f64> {
+        area.iter().map(|coordinates| self.height(coordinates)).collect()
     }
 
-    pub fn humidity(&self, coordinates: &Coordinates) -> f64 {
-        let (x,y) = coordinates.as_offset(&NoiseGenerator::MAP_CENTER);
-        self.humidity_source_module.get([-x as f64 / 512., -y as f64 / 512.])
+    /// Evaluates every channel of the area, in the same order
+    pub fn sample_area(&self, area: &[Coordinates]) -> Vec<Sample> {
+        self.sample_with_heights(area, &self.heights(area))
+    }
+
+    /// Evaluates the humidity and the temperature of the area from its heights, already known and in the same order
+    pub fn sample_with_heights(&self, area: &[Coordinates], heights: &[f64]) -> Vec<Sample> {
+        area.iter()
+            .zip(heights.iter())
+            .map(|(coordinates, height)| {
+                let point = self.noise_point(coordinates);
+                self.shared_height.set(*height);
+                Sample {
+                    height: *height,
+                    humidity: self.humidity_source_module.get(point),
+                    temperature: self.temperature_at(coordinates, point, *height),
+                }
+            })
+            .collect()
+    }
+
+    fn temperature_at(&self, coordinates: &Coordinates, point: [f64; 2], height: f64) -> f64 {
+        NoiseGenerator::latitude_temperature(coordinates.r) - NoiseGenerator::LAPSE_RATE * height.max(0.)
+            + self.temperature_source_module.get(point)
+    }
+
+    /// Temperature of the row, from 1 at the equator to -1 at the poles, rows beyond the poles staying polar
+    fn latitude_temperature(r: i32) -> f64 {
+        let latitude = (r.unsigned_abs() as f64).min(NoiseGenerator::POLE_DISTANCE) / NoiseGenerator::POLE_DISTANCE;
+        (latitude * PI).cos()
+    }
+
+    fn noise_point(&self, coordinates: &Coordinates) -> [f64; 2] {
+        let (x, y) = coordinates.world_position();
+        [x / self.world_scale, y / self.world_scale]
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::f64;
+
+    use area::Area;
+    use generator::{GeneratorParameters, NoiseGenerator};
+    use noise_graph::NoiseGraph;
+    use tiles::Coordinates;
+
+    #[test]
+    fn latitude_temperature_stays_polar_beyond_the_poles() {
+        let temperatures: Vec<f64> = [0, 150, -150, 300, -300, 100_000].iter()
+            .map(|r| NoiseGenerator::latitude_temperature(*r))
+            .collect();
+        assert!((temperatures[0] - 1.).abs() < 1e-9);
+        assert!(temperatures[1..].iter().all(|temperature| (temperature + 1.).abs() < 1e-9));
+        assert!((NoiseGenerator::latitude_temperature(75) - NoiseGenerator::latitude_temperature(-75)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_invalid_world_scales() {
+        let noise_graph = NoiseGraph::load("config/noise.ron").expect("Could not load the noise graph");
+        for world_scale in [0., -16., f64::NAN, f64::INFINITY].iter() {
+            let parameters = GeneratorParameters { seed: 0, humidity_scale: 0.97, humidity_bias: 0.1, world_scale: *world_scale };
+            let error = NoiseGenerator::new(&noise_graph, &parameters).err().expect("Invalid world scale accepted");
+            assert_eq!(error, format!("The world scale must be a positive number, got {}", world_scale));
+        }
+    }
+
+    #[test]
+    fn sampled_area_matches_each_hexagon() {
+        let noise_graph = NoiseGraph::load("config/noise.ron").expect("Could not load the noise graph");
+        let parameters = GeneratorParameters { seed: 3, humidity_scale: 0.97, humidity_bias: 0.1, world_scale: 16. };
+        let noise_generator = NoiseGenerator::new(&noise_graph, &parameters).expect("Could not build the noise generator");
+        let area = Area::hexagon(Coordinates { q: 40, r: -20 }, 6);
+
+        let samples = noise_generator.sample_area(area.coordinates());
+        assert_eq!(samples.len(), area.len());
+        for (coordinates, sample) in area.iter().zip(samples.iter()) {
+            let height = noise_generator.height(coordinates);
+            assert_eq!(sample.height, height);
+            assert_eq!(sample.humidity, noise_generator.humidity(coordinates));
+            assert_eq!(sample.temperature, noise_generator.temperature(coordinates, height));
+        }
+    }
+}
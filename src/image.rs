@@ -0,0 +1,105 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use tile_renderer::{Tint, TileRenderer};
+use tiles::{Coordinates, Hexagon};
+use ::{HEIGHT_SHIFT, PIXEL_PER_HEXAGON};
+
+/// Draws hexagons as flat colored polygons into an RGB buffer, without any display or graphics library
+pub struct ImageRenderer {
+    width: u32,
+    height: u32,
+    origin: (i32, i32),
+    pixels: Vec<(u8, u8, u8)>,
+}
+
+impl ImageRenderer {
+    /// Half of the width of an hexagon, matching the horizontal spacing of `Coordinates::as_offset`
+    const HALF_WIDTH: i32 = 16;
+    /// Distance from the center to the top vertex, matching the vertical spacing of `Coordinates::as_offset`
+    const VERTICAL_RADIUS: i32 = 14;
+
+    const COLOR_BACKGROUND: (u8, u8, u8) = (0, 0, 0);
+    const TINT_SHADOW: Tint = Tint { r: 0, g: 0, b: 0, a: 40 };
+
+    const SHADOW_SHIFT_X: i32 = PIXEL_PER_HEXAGON as i32 / 10;
+    const SHADOW_SHIFT_Y: i32 = -(PIXEL_PER_HEXAGON as i32) / 6;
+
+    pub fn new(width: u32, height: u32) -> Result<ImageRenderer, String> {
+        let pixel_count = (width as usize).checked_mul(height as usize)
+            .ok_or_else(|| format!("Image of {}x{} pixels is too large", width, height))?;
+        let mut pixels = Vec::new();
+        pixels.try_reserve_exact(pixel_count)
+            .map_err(|e| format!("Could not allocate an image of {}x{} pixels: {}", width, height, e))?;
+        pixels.resize(pixel_count, ImageRenderer::COLOR_BACKGROUND);
+        let origin = ((width / 2) as i32, (height / 2) as i32);
+        Ok(ImageRenderer { width, height, origin, pixels })
+    }
+
+    /// Writes the image as a binary PPM file
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Could not create image {}: {}", path, e))?;
+        let mut writer = BufWriter::new(file);
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)
+            .and_then(|_| self.pixels.iter().try_for_each(|(r, g, b)| writer.write_all(&[*r, *g, *b])))
+            .and_then(|_| writer.flush())
+            .map_err(|e| format!("Could not write image {}: {}", path, e))
+    }
+
+    fn center(&self, screen_center: &Coordinates, coordinates: &Coordinates, level: i32) -> (i32, i32) {
+        let (x_offset, y_offset) = coordinates.as_offset(screen_center);
+        (self.origin.0 + x_offset, self.origin.1 + y_offset + level * HEIGHT_SHIFT)
+    }
+
+    /// Applies the function to every pixel of the hexagon centered on the given pixel
+    fn fill_hexagon<F: Fn((u8, u8, u8)) -> (u8, u8, u8)>(&mut self, (center_x, center_y): (i32, i32), paint: F) {
+        for y in (center_y - ImageRenderer::VERTICAL_RADIUS).max(0)..=(center_y + ImageRenderer::VERTICAL_RADIUS).min(self.height as i32 - 1) {
+            for x in (center_x - ImageRenderer::HALF_WIDTH).max(0)..(center_x + ImageRenderer::HALF_WIDTH).min(self.width as i32) {
+                // Pointy top hexagon: the sides slope from the top vertex down to half of the vertical radius
+                let dx = (x - center_x).abs() * ImageRenderer::VERTICAL_RADIUS;
+                let dy = (y - center_y).abs() * 2 * ImageRenderer::HALF_WIDTH;
+                if dx + dy <= 2 * ImageRenderer::HALF_WIDTH * ImageRenderer::VERTICAL_RADIUS {
+                    let index = y as usize * self.width as usize + x as usize;
+                    self.pixels[index] = paint(self.pixels[index]);
+                }
+            }
+        }
+    }
+}
+
+impl TileRenderer for ImageRenderer {
+    fn clear(&mut self) {
+        self.pixels.iter_mut().for_each(|pixel| *pixel = ImageRenderer::COLOR_BACKGROUND);
+    }
+
+    fn draw_tile(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, hexagon: &Hexagon, level: i32) {
+        let color = hexagon.texture_type.1.color();
+        let center = self.center(screen_center, coordinates, level);
+        self.fill_hexagon(center, |_| color);
+    }
+
+    fn draw_shadow(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, level: i32) {
+        let (x, y) = self.center(screen_center, coordinates, level);
+        self.fill_hexagon((x + ImageRenderer::SHADOW_SHIFT_X, y + ImageRenderer::SHADOW_SHIFT_Y),
+                          |color| ImageRenderer::TINT_SHADOW.blend(color));
+    }
+
+    fn draw_overlay(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, level: i32, tint: Tint) {
+        let center = self.center(screen_center, coordinates, level);
+        self.fill_hexagon(center, |color| tint.blend(color));
+    }
+
+    fn present(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use image::ImageRenderer;
+
+    #[test]
+    fn rejects_images_too_large_to_allocate() {
+        assert!(ImageRenderer::new(u32::MAX, u32::MAX).is_err());
+        assert!(ImageRenderer::new(300, 200).is_ok());
+    }
+}
@@ -1,140 +1,213 @@
+#[cfg(feature = "terminal")]
+extern crate crossterm;
 extern crate noise;
-extern crate rand;
+extern crate ron;
+#[cfg(feature = "sdl")]
 extern crate sdl2;
+extern crate serde;
 
-use std::{thread, time};
+use std::convert::TryFrom;
+#[cfg(feature = "terminal")]
+use std::io;
 use std::error::Error;
 
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-
-use generator::NoiseGenerator;
-use renderer::Printer;
-use textures::Textures;
-use tiles::{Coordinates, Grid};
-
-mod tiles;
-mod textures;
-mod generator;
-mod divide;
-mod renderer;
-
-const LOGICAL_SCREEN_WIDTH: u32 = 1792;
-const LOGICAL_SCREEN_HEIGHT: u32 = 1120;
-const ORIGIN: (i32, i32) = ((LOGICAL_SCREEN_WIDTH / 2) as i32, (LOGICAL_SCREEN_HEIGHT / 2) as i32);
+#[cfg(feature = "terminal")]
+use crossterm::{event, execute};
+#[cfg(feature = "terminal")]
+use crossterm::cursor::{Hide, Show};
+#[cfg(feature = "terminal")]
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+
+use biomes::BiomeRules;
+#[cfg(feature = "terminal")]
+use explorer::Action;
+use explorer::Explorer;
+pub use generator::GeneratorParameters;
+use image::ImageRenderer;
+use noise_graph::NoiseGraph;
+#[cfg(feature = "terminal")]
+use terminal::TerminalRenderer;
+pub use tile_renderer::{DrawCall, RecordingRenderer, TileRenderer, Tint};
+pub use tiles::Coordinates;
+use world::World;
+#[cfg(feature = "sdl")]
+pub use viewer::run;
+#[cfg(feature = "sdl")]
+use viewer::render_textured;
+
+pub mod tiles;
+pub mod area;
+pub mod chunks;
+pub mod terrain;
+pub mod biomes;
+pub mod coastline;
+pub mod generator;
+pub mod divide;
+pub mod shared_value;
+pub mod noise_graph;
+pub mod rivers;
+pub mod pathfinding;
+pub mod tile_renderer;
+pub mod explorer;
+#[cfg(feature = "terminal")]
+pub mod terminal;
+pub mod image;
+pub mod world;
+pub mod workers;
+#[cfg(feature = "sdl")]
+pub mod textures;
+#[cfg(feature = "sdl")]
+pub mod renderer;
+#[cfg(feature = "sdl")]
+mod viewer;
+
+/// Space left around the rendered area, for elevated tiles and textures overflowing their hexagon
+const RENDER_MARGIN: i32 = 96;
+const BIOME_RULES_PATH: &str = "config/biomes.ron";
+const NOISE_GRAPH_PATH: &str = "config/noise.ron";
 
 // TODO constants class
 pub const PIXEL_PER_HEXAGON: u32 = 15;
 pub const FLAT_SIDE_LENGTH: f32 = 32. / 30.;
-
-pub fn run(full_screen: bool, width: u32, height: u32) -> Result<(), Box<dyn Error>> {
-    let sdl_context = sdl2::init()?;
-    let video_subsys = sdl_context.video()?;
-    let (display_width, display_height) = video_subsys.display_bounds(0)?.size();
-    println!("display {}x{}", display_width, display_height);
-
-    let mut canvas;
-    {
-        let mut builder = video_subsys.window("Auto dungeon", width, height);
-        if full_screen { builder.fullscreen_desktop(); } else { builder.borderless(); }
-
-        canvas = builder.opengl()
-            .build()?
-            .into_canvas()
-            .build()?;
-    }
-
-    // TODO Apparently scales the whole screen each time which is highly ineffective, see:
-    // https://stackoverflow.com/questions/11043969/how-to-scale-to-resolution-in-sdl
-    canvas.set_logical_size(LOGICAL_SCREEN_WIDTH, LOGICAL_SCREEN_HEIGHT)
-        .map_err(|e| e.to_string())?;
-
-    let texture_creator = canvas.texture_creator();
-    let mut textures = Textures::new(&texture_creator);
-
-    const GRID_RADIUS: i32 = 25;
-
-    let mut humidity_scale = 0.97;
-    let mut humidity_bias = 0.1;
-
-    let mut noise_generator = NoiseGenerator::new(0, humidity_scale, humidity_bias);
-    let mut center_coordinates = Coordinates { q: 0, r: 0 };
-    let mut area = Coordinates::build_hexagonal_area(center_coordinates, GRID_RADIUS);
-    let mut grid = Grid::new(&noise_generator, &area)?;
-
-    let mut printer = Printer::new(&mut canvas, ORIGIN, PIXEL_PER_HEXAGON);
-    grid.draw(&mut printer, center_coordinates, &mut textures, GRID_RADIUS);
-
-    let mut pristine = true;
-    let mut events = sdl_context.event_pump()?;
-    'main: loop {
-        for event in events.poll_iter() {
-            match event {
-                Event::Quit { .. } => break 'main,
-                Event::KeyDown { keycode: Option::Some(Keycode::Escape), .. } => break 'main,
-
-                // TODO more generic way of handling inputs
-                Event::KeyDown { keycode: Option::Some(Keycode::Left), .. } => {
-                    center_coordinates = center_coordinates.shift(-2, 0);
-                    area = Coordinates::build_hexagonal_area(center_coordinates, GRID_RADIUS);
-                    grid.at(&noise_generator, &area);
-                    pristine = false;
-                }
-                Event::KeyDown { keycode: Option::Some(Keycode::Right), .. } => {
-                    center_coordinates = center_coordinates.shift(2, 0);
-                    area = Coordinates::build_hexagonal_area(center_coordinates, GRID_RADIUS);
-                    grid.at(&noise_generator, &area);
-                    pristine = false;
-                }
-                Event::KeyDown { keycode: Option::Some(Keycode::Up), .. } => {
-                    center_coordinates = center_coordinates.shift(1, -2);
-                    area = Coordinates::build_hexagonal_area(center_coordinates, GRID_RADIUS);
-                    grid.at(&noise_generator, &area);
-                    pristine = false;
-                }
-                Event::KeyDown { keycode: Option::Some(Keycode::Down), .. } => {
-                    center_coordinates = center_coordinates.shift(-1, 2);
-                    area = Coordinates::build_hexagonal_area(center_coordinates, GRID_RADIUS);
-                    grid.at(&noise_generator, &area);
-                    pristine = false;
-                }
-                Event::KeyDown { keycode: Option::Some(Keycode::P), .. } => {
-                    humidity_bias += 0.1;
-                    noise_generator = NoiseGenerator::new(0, humidity_scale, humidity_bias);
-                    area = Coordinates::build_hexagonal_area(center_coordinates, GRID_RADIUS);
-                    grid = Grid::new(&noise_generator, &area)?;
-                    pristine = false;
-                }
-                Event::KeyDown { keycode: Option::Some(Keycode::M), .. } => {
-                    humidity_bias -= 0.1;
-                    noise_generator = NoiseGenerator::new(0, humidity_scale, humidity_bias);
-                    area = Coordinates::build_hexagonal_area(center_coordinates, GRID_RADIUS);
-                    grid = Grid::new(&noise_generator, &area)?;
-                    pristine = false;
-                }
-                Event::KeyDown { keycode: Option::Some(Keycode::O), .. } => {
-                    humidity_scale += 0.01;
-                    noise_generator = NoiseGenerator::new(0, humidity_scale, humidity_bias);
-                    area = Coordinates::build_hexagonal_area(center_coordinates, GRID_RADIUS);
-                    grid = Grid::new(&noise_generator, &area)?;
-                    pristine = false;
-                }
-                Event::KeyDown { keycode: Option::Some(Keycode::L), .. } => {
-                    humidity_scale -= 0.01;
-                    noise_generator = NoiseGenerator::new(0, humidity_scale, humidity_bias);
-                    area = Coordinates::build_hexagonal_area(center_coordinates, GRID_RADIUS);
-                    grid = Grid::new(&noise_generator, &area)?;
-                    pristine = false;
-                }
-                _ => {}
+/// Vertical shift of the hexagons for each elevation level, in pixels
+pub const HEIGHT_SHIFT: i32 = -26 * PIXEL_PER_HEXAGON as i32 / 30;
+
+/// Files the world is loaded from instead of being generated, and saved to once explored
+#[derive(Default, Debug)]
+pub struct WorldFiles {
+    pub load: Option<String>,
+    pub save: Option<String>,
+}
+
+impl WorldFiles {
+    fn save(&self, explorer: &Explorer) -> Result<(), String> {
+        match &self.save {
+            Some(path) => {
+                explorer.world().save(path)?;
+                println!("Saved world into {}", path);
+                Ok(())
             }
+            None => Ok(()),
         }
-        if !pristine {
-            grid.draw(&mut printer, center_coordinates, &mut textures, GRID_RADIUS);
-            pristine = true;
+    }
+}
+
+/// Settings of the explorer shared by every front end
+#[derive(Debug)]
+pub struct ExplorerSettings {
+    /// Maximum memory used by the generated hexagons, in bytes
+    pub memory_budget: usize,
+    /// Radius of the field of view of the center, the fog of war is disabled without it
+    pub sight: Option<i32>,
+}
+
+impl ExplorerSettings {
+    fn apply(&self, explorer: &mut Explorer) {
+        explorer.set_memory_budget(self.memory_budget);
+        if let Some(sight) = self.sight {
+            explorer.set_sight(sight);
         }
-        thread::sleep(time::Duration::from_millis(1024 / 32));
     }
+}
+
+/// Explorer of the loaded world, with its own center and radius, if any.
+/// Explorer of a world generated from the parameters otherwise.
+fn open_explorer<'a>(noise_graph: &'a NoiseGraph, biome_rules: &'a BiomeRules, parameters: GeneratorParameters,
+                     center_coordinates: Coordinates, radius: i32, files: &WorldFiles) -> Result<Explorer<'a>, Box<dyn Error>> {
+    match &files.load {
+        Some(path) => Explorer::from_world(noise_graph, biome_rules, World::load(path)?),
+        None => Explorer::new(noise_graph, biome_rules, parameters, center_coordinates, radius),
+    }
+}
+
+/// Explores the world in the terminal, with the same key bindings as `run`
+#[cfg(feature = "terminal")]
+pub fn run_terminal(parameters: GeneratorParameters, center_coordinates: Coordinates, settings: &ExplorerSettings,
+                    files: &WorldFiles) -> Result<(), Box<dyn Error>> {
+    let biome_rules = BiomeRules::load(BIOME_RULES_PATH)?;
+    let noise_graph = NoiseGraph::load(NOISE_GRAPH_PATH)?;
+
+    let (columns, rows) = crossterm::terminal::size()?;
+    let mut renderer = TerminalRenderer::new(columns, rows);
+    let radius = renderer.fitting_radius();
+    let mut explorer = open_explorer(&noise_graph, &biome_rules, parameters, center_coordinates, radius, files)?;
+    settings.apply(&mut explorer);
+    explorer.set_radius(radius);
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+    let result = explore_terminal(&mut explorer, &mut renderer);
+    // The terminal is restored even when the exploration failed
+    execute!(io::stdout(), Show, LeaveAlternateScreen)?;
+    disable_raw_mode()?;
+
+    result?;
+    files.save(&explorer)?;
+    Ok(())
+}
+
+#[cfg(feature = "terminal")]
+fn explore_terminal(explorer: &mut Explorer, renderer: &mut TerminalRenderer) -> Result<(), Box<dyn Error>> {
+    loop {
+        explorer.draw(renderer);
+        if let event::Event::Key(key_event) = event::read()? {
+            match terminal::key(key_event.code).and_then(Action::from_key) {
+                None => {}
+                Some(Action::Quit) => return Ok(()),
+                Some(action) => explorer.apply(action)?,
+            }
+        }
+    }
+}
+
+/// Renders the hexagonal area around the center into an image file, without any display.
+///
+/// PPM files are drawn with flat biome colors, other formats with the textures of the viewer.
+/// Loaded worlds are rendered with their own center and radius.
+pub fn render(parameters: GeneratorParameters, center_coordinates: Coordinates, radius: i32, output: &str,
+              settings: &ExplorerSettings, files: &WorldFiles) -> Result<(), Box<dyn Error>> {
+    if radius < 0 {
+        return Err(format!("The render radius must not be negative, got {}", radius).into());
+    }
+    if !output.ends_with(".png") && !output.ends_with(".ppm") {
+        return Err(format!("Cannot render {}, only PNG and PPM files are supported", output).into());
+    }
+    // Checked before generating anything, the radius of a loaded world is checked once it is loaded
+    image_size(radius)?;
+    let biome_rules = BiomeRules::load(BIOME_RULES_PATH)?;
+    let noise_graph = NoiseGraph::load(NOISE_GRAPH_PATH)?;
+
+    let mut explorer = open_explorer(&noise_graph, &biome_rules, parameters, center_coordinates, radius, files)?;
+    settings.apply(&mut explorer);
+    let radius = explorer.world().radius;
+
+    let (width, height) = image_size(radius)?;
+
+    if output.ends_with(".ppm") {
+        let mut image = ImageRenderer::new(width, height)?;
+        explorer.draw(&mut image);
+        image.save(output)?;
+    } else {
+        render_textured(&explorer, width, height, output)?;
+    }
+    println!("Rendered {} hexagons into {}", explorer.area().len(), output);
+    files.save(&explorer)?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Width and height of the image rendering the given radius, with its margin
+fn image_size(radius: i32) -> Result<(u32, u32), String> {
+    let (x_extent, _) = Coordinates { q: radius, r: 0 }.as_offset(&Coordinates { q: 0, r: 0 });
+    let (_, y_extent) = Coordinates { q: 0, r: radius }.as_offset(&Coordinates { q: 0, r: 0 });
+    let size = |extent: i32| extent.checked_add(RENDER_MARGIN)
+        .and_then(|half_size| half_size.checked_mul(2))
+        .and_then(|size| u32::try_from(size).ok())
+        .ok_or_else(|| format!("Cannot render a radius of {}, the image would be too large", radius));
+    Ok((size(x_extent)?, size(y_extent)?))
+}
+
+#[cfg(not(feature = "sdl"))]
+fn render_textured(_explorer: &Explorer, _width: u32, _height: u32, output: &str) -> Result<(), Box<dyn Error>> {
+    Err(format!("Rendering {} with textures needs the sdl feature, only PPM files can be rendered without it", output).into())
+}
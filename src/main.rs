@@ -1,13 +1,21 @@
+extern crate auto_dungeon;
 extern crate clap;
+extern crate rand;
 
+use std::error::Error;
 use std::process;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use auto_dungeon::{Coordinates, ExplorerSettings, GeneratorParameters, WorldFiles};
 
 /// Procedurally generated hexagon based world
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// Display the app in full screen
     #[clap(short, long, raw(false), parse(try_from_str), default_value = "true")]
     full_screen: bool,
@@ -19,15 +27,126 @@ struct Args {
     /// Height in pixels
     #[clap(short, long, default_value_t = 1080)]
     height: u32,
+
+    /// Seed of the generated world
+    #[clap(long, global = true, default_value_t = 0)]
+    seed: u32,
+
+    /// Generate the world from a random seed, printed at startup
+    #[clap(long, global = true, conflicts_with = "seed")]
+    random_seed: bool,
+
+    /// Scale applied to the humidity noise
+    #[clap(long, global = true, allow_hyphen_values = true, default_value_t = 0.97)]
+    humidity_scale: f64,
+
+    /// Bias added to the humidity noise
+    #[clap(long, global = true, allow_hyphen_values = true, default_value_t = 0.1)]
+    humidity_bias: f64,
+
+    /// Number of hexagons spanned by one unit of noise
+    #[clap(long, global = true, default_value_t = 16.)]
+    world_scale: f64,
+
+    /// Coordinates of the hexagon displayed at the center of the screen, as q,r
+    #[clap(long, global = true, allow_hyphen_values = true, default_value = "0,0")]
+    center: Coordinates,
+
+    /// Memory kept for the hexagons already generated, in MiB, the least recently visited are dropped beyond it
+    #[clap(long, global = true, default_value_t = 64)]
+    memory_budget: usize,
+
+    /// Load the world from a file saved with --save, instead of generating it
+    #[clap(long, global = true)]
+    load: Option<String>,
+
+    /// Save the world shown when leaving into a file
+    #[clap(long, global = true)]
+    save: Option<String>,
+
+    /// Only show what can be seen from the center up to this many hexagons away, and darkened what was seen before
+    #[clap(long, global = true)]
+    sight: Option<i32>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Render the world around the center into a PNG or PPM file, without opening a window
+    Render {
+        /// Radius of the rendered area, in hexagons
+        #[clap(short, long, default_value_t = 25)]
+        radius: i32,
+
+        /// Path of the image to write, PPM files are drawn with flat colors instead of textures
+        #[clap(short, long, default_value = "world.png")]
+        output: String,
+    },
+    /// Explore the world in the terminal, with the same key bindings as the window
+    Terminal,
 }
 
 fn main() {
     let args = Args::parse();
     println!("Running app with {:?}", args);
 
-    if let Err(e) = auto_dungeon::run(args.full_screen, args.width, args.height) {
+    let seed = if args.random_seed {
+        let seed = rand::random();
+        println!("Using random seed {}", seed);
+        seed
+    } else {
+        args.seed
+    };
+    let parameters = GeneratorParameters {
+        seed,
+        humidity_scale: args.humidity_scale,
+        humidity_bias: args.humidity_bias,
+        world_scale: args.world_scale,
+    };
+
+    let memory_budget = match args.memory_budget.checked_mul(1024 * 1024) {
+        Some(0) => {
+            println!("Invalid memory budget: at least 1 MiB is needed to keep the hexagons around the center");
+
+            process::exit(2);
+        }
+        Some(memory_budget) => memory_budget,
+        None => {
+            println!("Invalid memory budget: {} MiB is more than can be addressed", args.memory_budget);
+
+            process::exit(2);
+        }
+    };
+    let settings = ExplorerSettings { memory_budget, sight: args.sight };
+    let files = WorldFiles { load: args.load.clone(), save: args.save.clone() };
+
+    let result = match args.command {
+        Some(Command::Render { radius, ref output }) => auto_dungeon::render(parameters, args.center, radius, output, &settings, &files),
+        Some(Command::Terminal) => run_terminal(&args, parameters, &settings, &files),
+        None => run_window(&args, parameters, &settings, &files),
+    };
+    if let Err(e) = result {
         println!("Application error: {}", e);
 
         process::exit(1);
     }
 }
+
+#[cfg(feature = "sdl")]
+fn run_window(args: &Args, parameters: GeneratorParameters, settings: &ExplorerSettings, files: &WorldFiles) -> Result<(), Box<dyn Error>> {
+    auto_dungeon::run(args.full_screen, args.width, args.height, parameters, args.center, settings, files)
+}
+
+#[cfg(not(feature = "sdl"))]
+fn run_window(_args: &Args, _parameters: GeneratorParameters, _settings: &ExplorerSettings, _files: &WorldFiles) -> Result<(), Box<dyn Error>> {
+    Err("the window needs the sdl feature, use the terminal or render subcommands instead".into())
+}
+
+#[cfg(feature = "terminal")]
+fn run_terminal(args: &Args, parameters: GeneratorParameters, settings: &ExplorerSettings, files: &WorldFiles) -> Result<(), Box<dyn Error>> {
+    auto_dungeon::run_terminal(parameters, args.center, settings, files)
+}
+
+#[cfg(not(feature = "terminal"))]
+fn run_terminal(_args: &Args, _parameters: GeneratorParameters, _settings: &ExplorerSettings, _files: &WorldFiles) -> Result<(), Box<dyn Error>> {
+    Err("the terminal viewer needs the terminal feature, use the window or render subcommands instead".into())
+}
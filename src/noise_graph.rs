@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::fs;
+
+use noise::{Curve, Fbm, MultiFractal, NoiseFn, Perlin, ScaleBias, Terrace};
+use ron;
+use serde::Deserialize;
+
+use divide::Divide;
+use shared_value::SharedValue;
+
+/// Noise function of the graph, sources are referenced by their module name
+#[derive(Debug, Deserialize)]
+pub enum NoiseModule {
+    Perlin {
+        /// Added to the world seed, so modules of the same type output different values
+        seed_offset: u32,
+    },
+    Fbm {
+        seed_offset: u32,
+        #[serde(default)]
+        frequency: Option<f64>,
+        #[serde(default)]
+        persistence: Option<f64>,
+        #[serde(default)]
+        lacunarity: Option<f64>,
+        #[serde(default)]
+        octaves: Option<usize>,
+    },
+    Terrace {
+        source: String,
+        control_points: Vec<f64>,
+    },
+    Curve {
+        source: String,
+        /// (input, output) pairs
+        control_points: Vec<(f64, f64)>,
+    },
+    ScaleBias {
+        source: String,
+        #[serde(default)]
+        scale: Option<f64>,
+        #[serde(default)]
+        bias: Option<f64>,
+    },
+    Divide {
+        source1: String,
+        source2: String,
+    },
+}
+
+/// Named noise modules wired together, and the modules used for each channel of the `NoiseGenerator`.
+///
+/// Modules used by several others are built once for each of them, except the height channel which the humidity
+/// reads from the value computed for the point.
+#[derive(Debug, Deserialize)]
+pub struct NoiseGraph {
+    modules: HashMap<String, NoiseModule>,
+    height: String,
+    humidity: String,
+    temperature: String,
+}
+
+impl NoiseGraph {
+    pub fn load(path: &str) -> Result<NoiseGraph, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read noise graph {}: {}", path, e))?;
+        let graph: NoiseGraph = ron::from_str(&content)
+            .map_err(|e| format!("Could not parse noise graph {}: {}", path, e))?;
+        graph.validate()
+            .map_err(|e| format!("Invalid noise graph {}: {}", path, e))?;
+        Ok(graph)
+    }
+
+    pub fn height_function(&self, seed: u32) -> Result<Box<dyn NoiseFn<f64, 2>>, String> {
+        self.build(&self.height, seed, None, &mut Vec::new())
+    }
+
+    /// Humidity reading the height channel from the shared value, which must hold the height of the point
+    /// before each evaluation
+    pub fn humidity_function(&self, seed: u32, height: &SharedValue) -> Result<Box<dyn NoiseFn<f64, 2>>, String> {
+        self.build(&self.humidity, seed, Some(height), &mut Vec::new())
+    }
+
+    pub fn temperature_function(&self, seed: u32) -> Result<Box<dyn NoiseFn<f64, 2>>, String> {
+        self.build(&self.temperature, seed, None, &mut Vec::new())
+    }
+
+    /// Building every channel once is enough to detect missing modules, cycles and invalid control points
+    fn validate(&self) -> Result<(), String> {
+        [&self.height, &self.humidity, &self.temperature].iter()
+            .try_for_each(|name| self.build(name, 0, None, &mut Vec::new()).map(|_| ()))
+    }
+
+    /// Noise functions ignore control points whose input is already there, and panic without enough of them
+    fn check_control_points(kind: &str, name: &str, inputs: &[f64], minimum: usize) -> Result<(), String> {
+        for (index, input) in inputs.iter().enumerate() {
+            if inputs[..index].contains(input) {
+                return Err(format!("{} {} has the control point {} twice", kind, name, input));
+            }
+        }
+        if inputs.len() < minimum {
+            return Err(format!("{} {} needs at least {} control points", kind, name, minimum));
+        }
+        Ok(())
+    }
+
+    fn build<'a>(&'a self, name: &'a str, seed: u32, height: Option<&SharedValue>,
+                 dependents: &mut Vec<&'a str>) -> Result<Box<dyn NoiseFn<f64, 2>>, String> {
+        if let Some(height) = height.filter(|_| name == self.height) {
+            return Ok(Box::new(height.clone()));
+        }
+        if dependents.contains(&name) {
+            return Err(format!("noise module {} depends on itself through {}", name, dependents.join(" -> ")));
+        }
+        let module = self.modules.get(name)
+            .ok_or_else(|| format!("missing noise module {}", name))?;
+
+        dependents.push(name);
+        let function: Box<dyn NoiseFn<f64, 2>> = match module {
+            NoiseModule::Perlin { seed_offset } => Box::new(Perlin::new(seed.wrapping_add(*seed_offset))),
+            NoiseModule::Fbm { seed_offset, frequency, persistence, lacunarity, octaves } => {
+                let mut fbm = Fbm::<Perlin>::new(seed.wrapping_add(*seed_offset));
+                if let Some(frequency) = frequency {
+                    fbm = fbm.set_frequency(*frequency);
+                }
+                if let Some(persistence) = persistence {
+                    fbm = fbm.set_persistence(*persistence);
+                }
+                if let Some(lacunarity) = lacunarity {
+                    fbm = fbm.set_lacunarity(*lacunarity);
+                }
+                if let Some(octaves) = octaves {
+                    fbm = fbm.set_octaves(*octaves);
+                }
+                Box::new(fbm)
+            }
+            NoiseModule::Terrace { source, control_points } => {
+                NoiseGraph::check_control_points("terrace", name, control_points, 2)?;
+                let source = self.build(source, seed, height, dependents)?;
+                Box::new(control_points.iter()
+                    .fold(Terrace::new(source), |terrace, control_point| terrace.add_control_point(*control_point)))
+            }
+            NoiseModule::Curve { source, control_points } => {
+                let inputs: Vec<f64> = control_points.iter().map(|(input, _)| *input).collect();
+                NoiseGraph::check_control_points("curve", name, &inputs, 4)?;
+                let source = self.build(source, seed, height, dependents)?;
+                Box::new(control_points.iter()
+                    .fold(Curve::new(source), |curve, (input, output)| curve.add_control_point(*input, *output)))
+            }
+            NoiseModule::ScaleBias { source, scale, bias } => {
+                let mut scale_bias = ScaleBias::new(self.build(source, seed, height, dependents)?);
+                if let Some(scale) = scale {
+                    scale_bias = scale_bias.set_scale(*scale);
+                }
+                if let Some(bias) = bias {
+                    scale_bias = scale_bias.set_bias(*bias);
+                }
+                Box::new(scale_bias)
+            }
+            NoiseModule::Divide { source1, source2 } => {
+                let source1 = self.build(source1, seed, height, dependents)?;
+                let source2 = self.build(source2, seed, height, dependents)?;
+                Box::new(Divide::new(source1, source2))
+            }
+        };
+        dependents.pop();
+        Ok(function)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ron;
+
+    use noise_graph::NoiseGraph;
+
+    /// Graph whose three channels read the `channel` module, next to a `perlin` module
+    fn validate(modules: &str) -> Result<(), String> {
+        let content = format!(r#"#![enable(implicit_some)] (
+            modules: {{ "perlin": Perlin(seed_offset: 0), {} }},
+            height: "channel",
+            humidity: "channel",
+            temperature: "channel",
+        )"#, modules);
+        ron::from_str::<NoiseGraph>(&content)
+            .expect("Could not parse the test graph")
+            .validate()
+    }
+
+    #[test]
+    fn configured_graph_is_valid() {
+        NoiseGraph::load("config/noise.ron").expect("Could not load the noise graph");
+    }
+
+    #[test]
+    fn rejects_missing_modules() {
+        assert_eq!(validate(r#""channel": ScaleBias(source: "missing", scale: 2.)"#),
+                   Err("missing noise module missing".to_string()));
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        assert_eq!(validate(r#""channel": Divide(source1: "perlin", source2: "loop"), "loop": ScaleBias(source: "channel")"#),
+                   Err("noise module channel depends on itself through channel -> loop".to_string()));
+    }
+
+    #[test]
+    fn rejects_too_few_control_points() {
+        assert_eq!(validate(r#""channel": Terrace(source: "perlin", control_points: [0.5])"#),
+                   Err("terrace channel needs at least 2 control points".to_string()));
+        assert_eq!(validate(r#""channel": Curve(source: "perlin", control_points: [(-1., -1.), (0., 0.), (1., 1.)])"#),
+                   Err("curve channel needs at least 4 control points".to_string()));
+    }
+
+    #[test]
+    fn rejects_duplicate_control_points() {
+        assert_eq!(validate(r#""channel": Terrace(source: "perlin", control_points: [0.5, 0.5])"#),
+                   Err("terrace channel has the control point 0.5 twice".to_string()));
+        assert_eq!(validate(r#""channel": Curve(source: "perlin", control_points: [(-1., -1.), (0., 0.), (0., 1.), (1., 1.)])"#),
+                   Err("curve channel has the control point 0 twice".to_string()));
+    }
+}
@@ -0,0 +1,243 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use area::Area;
+use terrain::BiomeType;
+use tiles::{Coordinates, Grid, Hexagon};
+
+/// How hexagons are crossed, costs being added for each hexagon entered.
+///
+/// Only generated hexagons can be crossed.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Movement {
+    /// Whether deep water can be crossed, by boat for instance
+    pub cross_deep_water: bool,
+}
+
+impl Movement {
+    /// Lowest cost of entering a hexagon, so the A* heuristic never overestimates the remaining cost
+    const MINIMUM_COST: u32 = 2;
+    /// Cost of climbing one unit of height, going down is free
+    const CLIMB_COST: f64 = 10.;
+
+    /// Cost of moving from a hexagon to its neighbor, None when the neighbor cannot be entered
+    pub fn cost(&self, from: &Hexagon, to: &Hexagon) -> Option<u32> {
+        let (terrain, biome) = &to.texture_type;
+        if *biome == BiomeType::WDeep && !self.cross_deep_water {
+            return None;
+        }
+        // Water is crossed at the sea level, whatever its depth
+        let climb = (to.height.max(0.) - from.height.max(0.)).max(0.);
+        Some(biome.movement_cost() + terrain.movement_cost() + (climb * Movement::CLIMB_COST).round() as u32)
+    }
+
+    /// Neighbors of the hexagon with the total cost of reaching them, None for those that cannot be entered
+    fn steps(&self, grid: &Grid, coordinates: &Coordinates, cost: u32) -> [Option<(Coordinates, u32)>; 6] {
+        let hexagon = grid.get(coordinates);
+        coordinates.neighbors().map(|neighbor| {
+            let step_cost = self.cost(hexagon?, grid.get(&neighbor)?)?;
+            Some((neighbor, cost + step_cost))
+        })
+    }
+}
+
+/// Hexagons to explore, the one with the lowest priority first
+#[derive(PartialEq, Eq)]
+struct Step {
+    priority: u32,
+    cost: u32,
+    coordinates: Coordinates,
+}
+
+impl Ord for Step {
+    fn cmp(&self, other: &Step) -> Ordering {
+        // Reversed, as the binary heap pops the greatest step
+        other.priority.cmp(&self.priority)
+            .then_with(|| other.cost.cmp(&self.cost))
+            .then_with(|| (other.coordinates.q, other.coordinates.r).cmp(&(self.coordinates.q, self.coordinates.r)))
+    }
+}
+
+impl PartialOrd for Step {
+    fn partial_cmp(&self, other: &Step) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Cheapest way between two hexagons
+#[derive(Debug)]
+pub struct Path {
+    /// Hexagons crossed, from the start to the destination included
+    pub hexagons: Vec<Coordinates>,
+    /// Sum of the costs of every hexagon entered
+    pub cost: u32,
+}
+
+impl Path {
+    /// Cheapest path between the hexagons found with A*, None when the destination cannot be reached
+    pub fn find(grid: &Grid, from: &Coordinates, to: &Coordinates, movement: &Movement) -> Option<Path> {
+        grid.get(from)?;
+        grid.get(to)?;
+        let mut costs: HashMap<Coordinates, u32> = HashMap::from([(*from, 0)]);
+        let mut previous: HashMap<Coordinates, Coordinates> = HashMap::new();
+        let mut frontier = BinaryHeap::from([Step { priority: 0, cost: 0, coordinates: *from }]);
+
+        while let Some(Step { cost, coordinates, .. }) = frontier.pop() {
+            if coordinates == *to {
+                return Some(Path { hexagons: Path::walk_back(&previous, from, to), cost });
+            }
+            // Hexagons are queued again each time a cheaper way to them is found, the older entries are skipped
+            if cost > costs[&coordinates] {
+                continue;
+            }
+            for &(neighbor, neighbor_cost) in movement.steps(grid, &coordinates, cost).iter().flatten() {
+                if costs.get(&neighbor).is_none_or(|known_cost| neighbor_cost < *known_cost) {
+                    costs.insert(neighbor, neighbor_cost);
+                    previous.insert(neighbor, coordinates);
+                    let priority = neighbor_cost + Movement::MINIMUM_COST * neighbor.distance_to(to) as u32;
+                    frontier.push(Step { priority, cost: neighbor_cost, coordinates: neighbor });
+                }
+            }
+        }
+        None
+    }
+
+    fn walk_back(previous: &HashMap<Coordinates, Coordinates>, from: &Coordinates, to: &Coordinates) -> Vec<Coordinates> {
+        let mut hexagons = vec![*to];
+        let mut current = *to;
+        while current != *from {
+            current = previous[&current];
+            hexagons.push(current);
+        }
+        hexagons.reverse();
+        hexagons
+    }
+}
+
+/// Hexagons reachable from a start within a budget, with the lowest cost of reaching each of them.
+///
+/// Computed with Dijkstra's algorithm, stopping once every hexagon left costs more than the budget.
+pub struct ReachabilityMap {
+    costs: HashMap<Coordinates, u32>,
+    /// Reachable hexagons, from the cheapest to reach
+    reachable: Area,
+}
+
+impl ReachabilityMap {
+    pub fn new(grid: &Grid, from: &Coordinates, budget: u32, movement: &Movement) -> ReachabilityMap {
+        let mut costs: HashMap<Coordinates, u32> = HashMap::new();
+        let mut reachable: Vec<Coordinates> = Vec::new();
+        let mut frontier = BinaryHeap::new();
+        if grid.get(from).is_some() {
+            costs.insert(*from, 0);
+            frontier.push(Step { priority: 0, cost: 0, coordinates: *from });
+        }
+
+        while let Some(Step { cost, coordinates, .. }) = frontier.pop() {
+            if cost > costs[&coordinates] {
+                continue;
+            }
+            reachable.push(coordinates);
+            for &(neighbor, neighbor_cost) in movement.steps(grid, &coordinates, cost).iter().flatten() {
+                if neighbor_cost <= budget && costs.get(&neighbor).is_none_or(|known_cost| neighbor_cost < *known_cost) {
+                    costs.insert(neighbor, neighbor_cost);
+                    frontier.push(Step { priority: neighbor_cost, cost: neighbor_cost, coordinates: neighbor });
+                }
+            }
+        }
+
+        ReachabilityMap { costs, reachable: reachable.into_iter().collect() }
+    }
+
+    /// Lowest cost of reaching the hexagon, None when it is out of the budget
+    pub fn cost(&self, coordinates: &Coordinates) -> Option<u32> {
+        self.costs.get(coordinates).cloned()
+    }
+
+    pub fn reachable(&self) -> &Area {
+        &self.reachable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use area::Area;
+    use pathfinding::{Movement, Path, ReachabilityMap};
+    use terrain::{BiomeType, TerrainType};
+    use tiles::{Coordinates, Grid, Hexagon};
+
+    const ORIGIN: Coordinates = Coordinates { q: 0, r: 0 };
+
+    /// Flat temperate grid around the origin, costing 2 per hexagon entered, except where `special` says otherwise
+    fn grid<F: Fn(&Coordinates) -> Option<Hexagon>>(special: F) -> Grid {
+        Grid::from_hexagons(Area::hexagon(ORIGIN, 4).into_iter().map(|coordinates| {
+            let hexagon = special(&coordinates).unwrap_or_else(|| Hexagon::new((TerrainType::Flat, BiomeType::Temperate), 0.));
+            (coordinates, hexagon)
+        }))
+    }
+
+    #[test]
+    fn goes_around_mountains() {
+        let grid = grid(|coordinates| Some(Hexagon::new((TerrainType::Mont, BiomeType::Temperate), 0.)).filter(|_| *coordinates == ORIGIN));
+        let (from, to) = (Coordinates { q: -2, r: 0 }, Coordinates { q: 2, r: 0 });
+        let path = Path::find(&grid, &from, &to, &Movement::default()).expect("No path found");
+        assert_eq!(path.cost, 10);
+        assert_eq!(path.hexagons.len(), 6);
+        assert_eq!((path.hexagons.first(), path.hexagons.last()), (Some(&from), Some(&to)));
+        assert!(!path.hexagons.contains(&ORIGIN));
+    }
+
+    #[test]
+    fn deep_water_needs_a_boat() {
+        let grid = grid(|coordinates| Some(Hexagon::new((TerrainType::Flat, BiomeType::WDeep), -1.)).filter(|_| coordinates.q == 0));
+        let (from, to) = (Coordinates { q: -2, r: 0 }, Coordinates { q: 2, r: 0 });
+        assert!(Path::find(&grid, &from, &to, &Movement::default()).is_none());
+        let path = Path::find(&grid, &from, &to, &Movement { cross_deep_water: true }).expect("No path found");
+        assert_eq!(path.cost, 2 + 10 + 2 + 2);
+    }
+
+    #[test]
+    fn climbing_costs_more_than_descending() {
+        let top = Coordinates { q: 1, r: 0 };
+        let grid = grid(|coordinates| Some(Hexagon::new((TerrainType::Flat, BiomeType::Temperate), 1.)).filter(|_| *coordinates == top));
+        let up = Path::find(&grid, &ORIGIN, &top, &Movement::default()).expect("No path up");
+        let down = Path::find(&grid, &top, &ORIGIN, &Movement::default()).expect("No path down");
+        assert_eq!((up.cost, down.cost), (12, 2));
+    }
+
+    #[test]
+    fn missing_hexagons_cannot_be_reached() {
+        let grid = grid(|_| None);
+        assert!(Path::find(&grid, &ORIGIN, &Coordinates { q: 5, r: 0 }, &Movement::default()).is_none());
+        assert!(Path::find(&grid, &Coordinates { q: 5, r: 0 }, &ORIGIN, &Movement::default()).is_none());
+    }
+
+    #[test]
+    fn reachability_budget_is_inclusive() {
+        let grid = grid(|_| None);
+        let reachability_map = ReachabilityMap::new(&grid, &ORIGIN, 4, &Movement::default());
+        assert_eq!(reachability_map.reachable().len(), Area::hexagon(ORIGIN, 2).len());
+        assert!(Area::hexagon(ORIGIN, 2).iter().all(|coordinates| reachability_map.reachable().contains(coordinates)));
+        assert_eq!(reachability_map.cost(&Coordinates { q: 2, r: 0 }), Some(4));
+        assert_eq!(reachability_map.cost(&Coordinates { q: 3, r: 0 }), None);
+
+        let reachability_map = ReachabilityMap::new(&grid, &ORIGIN, 3, &Movement::default());
+        assert_eq!(reachability_map.reachable().len(), 7);
+    }
+
+    #[test]
+    fn minimum_cost_never_overestimates() {
+        let terrains = [TerrainType::Flat, TerrainType::Hill, TerrainType::Mont, TerrainType::OFlat];
+        let biomes = [BiomeType::Snow, BiomeType::WDeep, BiomeType::WShallow, BiomeType::Swamp, BiomeType::Boreal,
+            BiomeType::Temperate, BiomeType::Warm, BiomeType::Desert, BiomeType::Stone];
+        let movement = Movement { cross_deep_water: true };
+        let from = Hexagon::new((TerrainType::Flat, BiomeType::Temperate), 0.);
+        for terrain in terrains.iter() {
+            for biome in biomes.iter() {
+                let to = Hexagon::new((terrain.clone(), biome.clone()), 0.);
+                let cost = movement.cost(&from, &to).expect("Every hexagon can be entered");
+                assert!(cost >= Movement::MINIMUM_COST, "{:?} {:?} costs {}", terrain, biome, cost);
+            }
+        }
+    }
+}
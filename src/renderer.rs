@@ -1,14 +1,38 @@
 use sdl2::rect::{Point, Rect};
-use sdl2::render::{Canvas, Texture};
-use sdl2::video::Window;
+use sdl2::render::{Canvas, RenderTarget};
 use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::keyboard::Keycode;
 
-use tiles::Coordinates;
+use explorer::Key;
+use tiles::{Coordinates, Hexagon};
 use sdl2::pixels::Color;
-use ::{PIXEL_PER_HEXAGON, FLAT_SIDE_LENGTH};
+use textures::Textures;
+use tile_renderer::{Tint, TileRenderer};
+use ::{PIXEL_PER_HEXAGON, FLAT_SIDE_LENGTH, HEIGHT_SHIFT};
 
-pub struct Printer<'a> {
-    canvas: &'a mut Canvas<Window>,
+/// Key of the explorer bindings matching an SDL key
+pub fn key(keycode: Keycode) -> Option<Key> {
+    match keycode {
+        Keycode::Left => Some(Key::Left),
+        Keycode::Right => Some(Key::Right),
+        Keycode::Up => Some(Key::Up),
+        Keycode::Down => Some(Key::Down),
+        Keycode::Escape => Some(Key::Escape),
+        _ => {
+            let name = keycode.name();
+            let mut characters = name.chars();
+            match (characters.next(), characters.next()) {
+                (Some(character), None) => Some(Key::Char(character)),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Draws tiles on any canvas, whether it renders to a window, a surface or a texture
+pub struct Printer<'a, T: RenderTarget, C> {
+    canvas: &'a mut Canvas<T>,
+    textures: Textures<'a, C>,
 
     origin: (i32, i32),
     texture_ratio: u32,
@@ -18,7 +42,7 @@ pub struct Printer<'a> {
     shadow_y_template: [i32; 6],
 }
 
-impl<'a> Printer<'a> {
+impl<'a, T: RenderTarget, C> Printer<'a, T, C> {
     const X_TEMPLATE: [f32; 6] = [0., FLAT_SIDE_LENGTH, FLAT_SIDE_LENGTH, 0., -FLAT_SIDE_LENGTH, -FLAT_SIDE_LENGTH];
     const Y_TEMPLATE: [f32; 6] = [1., 0.5, -0.5, -1., -0.5, 0.5];
 
@@ -28,48 +52,61 @@ impl<'a> Printer<'a> {
     const PRINT_RECTANGLE_TEXTURE_WIDTH: u32 = 32;
     const PRINT_RECTANGLE_TEXTURE_HEIGHT: u32 = 48;
 
-    const HEIGHT_SHIFT: i32 = -26 * PIXEL_PER_HEXAGON as i32 / 30;
     const SHADOW_SHIFT_X: i32 = PIXEL_PER_HEXAGON as i32 / 10;
     const SHADOW_SHIFT_Y: i32 = -(PIXEL_PER_HEXAGON as i32) / 6;
 
-    pub fn new(canvas: &'a mut Canvas<Window>, origin: (i32, i32), pixel_per_hexagon: u32) -> Printer<'a> {
+    pub fn new(canvas: &'a mut Canvas<T>, textures: Textures<'a, C>, origin: (i32, i32), pixel_per_hexagon: u32) -> Printer<'a, T, C> {
         let texture_ratio = (pixel_per_hexagon as f32 * 2. / 30.).round() as u32;
-        let shadow_x_template = Printer::X_TEMPLATE.map(|f| (f * pixel_per_hexagon as f32).round() as i32);
-        let shadow_y_template = Printer::Y_TEMPLATE.map(|f| (f * pixel_per_hexagon as f32).round() as i32);
+        let shadow_x_template = Self::X_TEMPLATE.map(|f| (f * pixel_per_hexagon as f32).round() as i32);
+        let shadow_y_template = Self::Y_TEMPLATE.map(|f| (f * pixel_per_hexagon as f32).round() as i32);
         let tile_center_offset = (48. - 30.) / 2.;
         let pixel_ratio = pixel_per_hexagon as f32 / 30.;
         let tile_center_offset_pixel = tile_center_offset * pixel_ratio;
 
-        Printer { canvas, origin, texture_ratio, tile_center_offset_pixel, shadow_x_template, shadow_y_template }
+        Printer { canvas, textures, origin, texture_ratio, tile_center_offset_pixel, shadow_x_template, shadow_y_template }
     }
-    
-    pub fn clear(&mut self) {
-        self.canvas.set_draw_color(Printer::COLOR_BLACK);
+}
+
+impl<'a, T: RenderTarget, C> TileRenderer for Printer<'a, T, C> {
+    fn clear(&mut self) {
+        self.canvas.set_draw_color(Self::COLOR_BLACK);
         self.canvas.clear();
     }
-    
-    pub fn present(&mut self) {
-        self.canvas.present();
-    }
 
-    pub fn print_texture(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, texture: &Texture, elevation: u8) {
+    fn draw_tile(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, hexagon: &Hexagon, level: i32) {
         let (x_offset, y_offset) = coordinates.as_offset(screen_center);
-        let center = Point::new((self.origin.0 + x_offset) as i32, self.origin.1 + y_offset + self.tile_center_offset_pixel.round() as i32 + elevation as i32 * Printer::HEIGHT_SHIFT);
+        let center = Point::new((self.origin.0 + x_offset) as i32, self.origin.1 + y_offset + self.tile_center_offset_pixel.round() as i32 + level * HEIGHT_SHIFT);
         let texture_destination = Rect::from_center(center,
-                                                    Printer::PRINT_RECTANGLE_TEXTURE_WIDTH * self.texture_ratio,
-                                                    Printer::PRINT_RECTANGLE_TEXTURE_HEIGHT * self.texture_ratio);
+                                                    Self::PRINT_RECTANGLE_TEXTURE_WIDTH * self.texture_ratio,
+                                                    Self::PRINT_RECTANGLE_TEXTURE_HEIGHT * self.texture_ratio);
+        let texture = self.textures.random_texture(&hexagon.texture_type, coordinates);
         self.canvas.copy(texture, None, texture_destination)
             .expect("Could not create texture");
     }
 
-    pub fn print_shadow(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, elevation: u8) {
+    fn draw_shadow(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, level: i32) {
         let (x_offset, y_offset) = coordinates.as_offset(screen_center);
-        let x_template_shift = self.origin.0 + x_offset + Printer::SHADOW_SHIFT_X as i32;
-        let y_template_shift = self.origin.1 + y_offset + Printer::SHADOW_SHIFT_Y as i32 + elevation as i32 * Printer::HEIGHT_SHIFT;
-        
+        let x_template_shift = self.origin.0 + x_offset + Self::SHADOW_SHIFT_X as i32;
+        let y_template_shift = self.origin.1 + y_offset + Self::SHADOW_SHIFT_Y as i32 + level * HEIGHT_SHIFT;
+
         self.canvas.filled_polygon(&self.shadow_x_template.map(|val| (val + x_template_shift) as i16),
                                    &self.shadow_y_template.map(|val| (val + y_template_shift) as i16),
-                                   Printer::COLOR_SHADOW)
+                                   Self::COLOR_SHADOW)
             .expect("Could not create shadow polygon")
     }
-}
\ No newline at end of file
+
+    fn draw_overlay(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, level: i32, tint: Tint) {
+        let (x_offset, y_offset) = coordinates.as_offset(screen_center);
+        let x_template_shift = self.origin.0 + x_offset;
+        let y_template_shift = self.origin.1 + y_offset + level * HEIGHT_SHIFT;
+
+        self.canvas.filled_polygon(&self.shadow_x_template.map(|val| (val + x_template_shift) as i16),
+                                   &self.shadow_y_template.map(|val| (val + y_template_shift) as i16),
+                                   Color::RGBA(tint.r, tint.g, tint.b, tint.a))
+            .expect("Could not create overlay polygon")
+    }
+
+    fn present(&mut self) {
+        self.canvas.present();
+    }
+}
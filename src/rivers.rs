@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use area::Area;
+use terrain::BiomeType;
+use tiles::Coordinates;
+
+/// Drainage computed from the height field, used to carve rivers into the land.
+///
+/// Every land hexagon flows towards its lowest neighbor, and the drainage of a hexagon is the number of
+/// upstream hexagons whose flow passes through it. Flows are only followed for `CATCHMENT_RADIUS` steps,
+/// so the drainage of a hexagon never depends on the loaded area and rivers match across `Grid::at` calls.
+pub struct RiverMap {
+    drainage: HashMap<Coordinates, u32>,
+}
+
+impl RiverMap {
+    /// Number of steps a flow is followed for, the heights must cover the area grown by one more hexagon
+    pub const CATCHMENT_RADIUS: i32 = 16;
+    const SHALLOW_RIVER_THRESHOLD: u32 = 12;
+    const DEEP_RIVER_THRESHOLD: u32 = 48;
+
+    /// Drainage of the area, flows stop where the heights end
+    pub fn new(area: &Area, heights: &HashMap<Coordinates, f64>) -> RiverMap {
+        let mut drainage: HashMap<Coordinates, u32> = area.iter().map(|coordinates| (*coordinates, 0)).collect();
+        let mut flow_directions: HashMap<Coordinates, Option<Coordinates>> = HashMap::new();
+
+        // Any hexagon close enough to the area can flow into it, they all have to be followed
+        for source in area.expand(RiverMap::CATCHMENT_RADIUS) {
+            let mut current = source;
+            for _ in 0..RiverMap::CATCHMENT_RADIUS {
+                match RiverMap::flow_direction(heights, &mut flow_directions, current) {
+                    None => break,
+                    Some(next) => {
+                        if let Some(value) = drainage.get_mut(&next) {
+                            *value += 1;
+                        }
+                        current = next;
+                    }
+                }
+            }
+        }
+
+        RiverMap { drainage }
+    }
+
+    /// Biome of the river running through the hexagon, if any
+    pub fn river_biome(&self, coordinates: &Coordinates) -> Option<BiomeType> {
+        match self.drainage.get(coordinates) {
+            Some(drainage) if *drainage >= RiverMap::DEEP_RIVER_THRESHOLD => Some(BiomeType::WDeep),
+            Some(drainage) if *drainage >= RiverMap::SHALLOW_RIVER_THRESHOLD => Some(BiomeType::WShallow),
+            _ => None
+        }
+    }
+
+    fn flow_direction(heights: &HashMap<Coordinates, f64>,
+                      flow_directions: &mut HashMap<Coordinates, Option<Coordinates>>,
+                      coordinates: Coordinates) -> Option<Coordinates> {
+        if let Some(direction) = flow_directions.get(&coordinates) {
+            return *direction;
+        }
+
+        let neighbor_heights: Option<Vec<(Coordinates, f64)>> = coordinates.neighbors()
+            .iter()
+            .map(|neighbor| heights.get(neighbor).map(|neighbor_height| (*neighbor, *neighbor_height)))
+            .collect();
+        // Water flowing into the sea stops there, as well as water reaching a pit or the edge of the heights
+        let direction = match (heights.get(&coordinates), neighbor_heights) {
+            (Some(own_height), Some(neighbor_heights)) if *own_height >= 0. => neighbor_heights.into_iter()
+                .filter(|(_, neighbor_height)| neighbor_height < own_height)
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("Height should never be NaN"))
+                .map(|(neighbor, _)| neighbor),
+            _ => None,
+        };
+        flow_directions.insert(coordinates, direction);
+        direction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use area::Area;
+    use biomes::BiomeRules;
+    use generator::{GeneratorParameters, NoiseGenerator};
+    use noise_graph::NoiseGraph;
+    use rivers::RiverMap;
+    use terrain::BiomeType;
+    use tiles::{Coordinates, Grid};
+
+    const ORIGIN: Coordinates = Coordinates { q: 0, r: 0 };
+
+    /// Heights of the hexagons the drainage of the area depends on
+    fn heights<F: Fn(&Coordinates) -> f64>(area: &Area, height: F) -> HashMap<Coordinates, f64> {
+        area.expand(RiverMap::CATCHMENT_RADIUS + 1).iter()
+            .map(|coordinates| (*coordinates, height(coordinates)))
+            .collect()
+    }
+
+    #[test]
+    fn flows_towards_the_lowest_neighbor() {
+        let slope = heights(&Area::hexagon(ORIGIN, 0), |coordinates| coordinates.q as f64 + 2. * coordinates.r as f64 + 100.);
+        let direction = RiverMap::flow_direction(&slope, &mut HashMap::new(), ORIGIN);
+        // (0, -1) is 2 below the origin, (1, -1) and (-1, 0) only 1
+        assert_eq!(direction, Some(Coordinates { q: 0, r: -1 }));
+    }
+
+    #[test]
+    fn stops_in_the_sea_and_in_pits() {
+        let sea = heights(&Area::hexagon(ORIGIN, 0), |_| -1.);
+        assert_eq!(RiverMap::flow_direction(&sea, &mut HashMap::new(), ORIGIN), None);
+        let pit = heights(&Area::hexagon(ORIGIN, 0), |coordinates| ORIGIN.distance_to(coordinates) as f64);
+        assert_eq!(RiverMap::flow_direction(&pit, &mut HashMap::new(), ORIGIN), None);
+    }
+
+    #[test]
+    fn stops_at_the_edge_of_the_heights() {
+        let slope = |coordinates: &Coordinates| coordinates.q as f64 + 2. * coordinates.r as f64 + 100.;
+        let edge = Coordinates { q: 2, r: 0 };
+        let heights: HashMap<Coordinates, f64> = Area::hexagon(ORIGIN, 2).iter()
+            .map(|coordinates| (*coordinates, slope(coordinates)))
+            .collect();
+        assert_eq!(RiverMap::flow_direction(&heights, &mut HashMap::new(), edge), None);
+    }
+
+    #[test]
+    fn drainage_only_needs_the_catchment_heights() {
+        let area = Area::hexagon(ORIGIN, 3);
+        let slope = |coordinates: &Coordinates| (coordinates.q + 2 * coordinates.r + 100) as f64;
+        let wider_heights = heights(&area.expand(RiverMap::CATCHMENT_RADIUS), slope);
+        assert_eq!(RiverMap::new(&area, &heights(&area, slope)).drainage, RiverMap::new(&area, &wider_heights).drainage);
+    }
+
+    #[test]
+    fn drainage_thresholds() {
+        let river_map = RiverMap {
+            drainage: [(0, 11), (1, 12), (2, 47), (3, 48)].iter()
+                .map(|(q, drainage)| (Coordinates { q: *q, r: 0 }, *drainage))
+                .collect(),
+        };
+        let biomes: Vec<Option<BiomeType>> = (0..5).map(|q| river_map.river_biome(&Coordinates { q, r: 0 })).collect();
+        assert_eq!(biomes, [None, Some(BiomeType::WShallow), Some(BiomeType::WShallow), Some(BiomeType::WDeep), None]);
+    }
+
+    #[test]
+    fn funnel_drains_into_a_deep_river() {
+        // The 90 hexagons around the origin flow towards it, the flat plateau beyond does not flow anywhere
+        let funnel = |coordinates: &Coordinates| ORIGIN.distance_to(coordinates).min(5) as f64;
+        let area = Area::hexagon(ORIGIN, 5);
+        let river_map = RiverMap::new(&area, &heights(&area, funnel));
+        assert_eq!(river_map.river_biome(&ORIGIN), Some(BiomeType::WDeep));
+        assert_eq!(river_map.river_biome(&Coordinates { q: 5, r: 0 }), None);
+    }
+
+    #[test]
+    fn plateau_has_no_river() {
+        let area = Area::hexagon(ORIGIN, 3);
+        let river_map = RiverMap::new(&area, &heights(&area, |_| 1.));
+        assert!(area.iter().all(|coordinates| river_map.river_biome(coordinates).is_none()));
+    }
+
+    #[test]
+    fn water_is_never_above_the_sea_level() {
+        let noise_graph = NoiseGraph::load("config/noise.ron").expect("Could not load the noise graph");
+        let biome_rules = BiomeRules::load("config/biomes.ron").expect("Could not load the biome rules");
+        let parameters = GeneratorParameters { seed: 0, humidity_scale: 0.97, humidity_bias: 0.1, world_scale: 16. };
+        let noise_generator = NoiseGenerator::new(&noise_graph, &parameters).expect("Could not build the noise generator");
+        let hexagons = Grid::generate_hexagons(&noise_generator, &biome_rules, &Area::hexagon(ORIGIN, 25));
+
+        let water: Vec<_> = hexagons.values()
+            .filter(|hexagon| matches!(hexagon.texture_type.1, BiomeType::WShallow | BiomeType::WDeep))
+            .collect();
+        assert!(!water.is_empty());
+        assert!(water.iter().all(|hexagon| hexagon.height <= 0. && hexagon.level <= 0));
+    }
+}
@@ -0,0 +1,28 @@
+use noise::NoiseFn;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Noise function that outputs a value set from outside, whatever the point.
+///
+/// Lets a function reuse a value already computed for the same point, such as the height, instead of
+/// evaluating its sources a second time. Clones share the same value.
+#[derive(Clone, Default)]
+pub struct SharedValue {
+    value: Rc<Cell<f64>>,
+}
+
+impl SharedValue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, value: f64) {
+        self.value.set(value);
+    }
+}
+
+impl<T, const DIM: usize> NoiseFn<T, DIM> for SharedValue {
+    fn get(&self, _point: [T; DIM]) -> f64 {
+        self.value.get()
+    }
+}
@@ -0,0 +1,132 @@
+use std::io;
+use std::io::{Stdout, Write};
+
+use crossterm::cursor::MoveTo;
+use crossterm::event::KeyCode;
+use crossterm::queue;
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+
+use explorer::Key;
+use terrain::TerrainType;
+use tile_renderer::{Tint, TileRenderer};
+use tiles::{Coordinates, Hexagon};
+
+/// Key of the explorer bindings matching a terminal key
+pub fn key(code: KeyCode) -> Option<Key> {
+    match code {
+        KeyCode::Left => Some(Key::Left),
+        KeyCode::Right => Some(Key::Right),
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Esc => Some(Key::Escape),
+        KeyCode::Char(character) => Some(Key::Char(character)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Cell {
+    glyph: char,
+    background: (u8, u8, u8),
+}
+
+/// Draws hexagons as two characters wide cells of a terminal, using 24-bit ANSI colors.
+///
+/// Rows are shifted by half a hexagon, a cell per row, so neighbors stay next to each other.
+pub struct TerminalRenderer {
+    output: Stdout,
+    columns: u16,
+    rows: u16,
+    cells: Vec<Option<Cell>>,
+}
+
+impl TerminalRenderer {
+    /// Brightness added to the biome color for each level above the sea
+    const LEVEL_LIGHTENING: i32 = 12;
+    const COLOR_GLYPH: Color = Color::Rgb { r: 20, g: 20, b: 20 };
+
+    pub fn new(columns: u16, rows: u16) -> TerminalRenderer {
+        TerminalRenderer { output: io::stdout(), columns, rows, cells: vec![None; columns as usize * rows as usize] }
+    }
+
+    /// Largest radius of an hexagonal area fitting in the terminal
+    pub fn fitting_radius(&self) -> i32 {
+        ((self.columns as i32 - 2) / 4).min((self.rows as i32 - 1) / 2).max(0)
+    }
+
+    fn terrain_glyph(terrain: &TerrainType) -> char {
+        match terrain {
+            TerrainType::Flat => ' ',
+            TerrainType::OFlat => '.',
+            TerrainType::Hill => 'n',
+            TerrainType::Mont => '^',
+        }
+    }
+
+    fn lighten((r, g, b): (u8, u8, u8), level: i32) -> (u8, u8, u8) {
+        let shift = |component: u8| (component as i32 + level.max(0) * Self::LEVEL_LIGHTENING).min(255) as u8;
+        (shift(r), shift(g), shift(b))
+    }
+
+    /// Index of the left character of the hexagon cell, if it is on screen
+    fn cell_index(&self, screen_center: &Coordinates, coordinates: &Coordinates) -> Option<usize> {
+        let row = coordinates.r - screen_center.r + self.rows as i32 / 2;
+        let column = 2 * (coordinates.q - screen_center.q) + (coordinates.r - screen_center.r) + self.columns as i32 / 2 - 1;
+        if row < 0 || row >= self.rows as i32 || column < 0 || column + 1 >= self.columns as i32 {
+            return None;
+        }
+        Some(row as usize * self.columns as usize + column as usize)
+    }
+}
+
+impl TileRenderer for TerminalRenderer {
+    fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = None);
+    }
+
+    fn draw_tile(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, hexagon: &Hexagon, level: i32) {
+        if let Some(index) = self.cell_index(screen_center, coordinates) {
+            let (terrain, biome) = &hexagon.texture_type;
+            let background = Self::lighten(biome.color(), level);
+            self.cells[index] = Some(Cell { glyph: Self::terrain_glyph(terrain), background });
+            self.cells[index + 1] = Some(Cell { glyph: ' ', background });
+        }
+    }
+
+    /// Tiles are seen from above, raised hexagons are only lighter
+    fn draw_shadow(&mut self, _screen_center: &Coordinates, _coordinates: &Coordinates, _level: i32) {}
+
+    fn draw_overlay(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, _level: i32, tint: Tint) {
+        if let Some(index) = self.cell_index(screen_center, coordinates) {
+            for cell in self.cells[index..=index + 1].iter_mut().flatten() {
+                cell.background = tint.blend(cell.background);
+            }
+        }
+    }
+
+    fn draw_placeholder(&mut self, screen_center: &Coordinates, coordinates: &Coordinates) {
+        if let Some(index) = self.cell_index(screen_center, coordinates) {
+            let background = (Tint::PLACEHOLDER.r, Tint::PLACEHOLDER.g, Tint::PLACEHOLDER.b);
+            self.cells[index] = Some(Cell { glyph: '?', background });
+            self.cells[index + 1] = Some(Cell { glyph: ' ', background });
+        }
+    }
+
+    fn present(&mut self) {
+        for row in 0..self.rows {
+            queue!(self.output, MoveTo(0, row)).expect("Could not move the terminal cursor");
+            let row_cells = &self.cells[row as usize * self.columns as usize..(row as usize + 1) * self.columns as usize];
+            for cell in row_cells {
+                match cell {
+                    None => queue!(self.output, ResetColor, Print(' ')),
+                    Some(Cell { glyph, background: (r, g, b) }) => queue!(self.output,
+                        SetBackgroundColor(Color::Rgb { r: *r, g: *g, b: *b }),
+                        SetForegroundColor(Self::COLOR_GLYPH),
+                        Print(*glyph)),
+                }.expect("Could not write to the terminal");
+            }
+        }
+        queue!(self.output, ResetColor).expect("Could not write to the terminal");
+        self.output.flush().expect("Could not flush the terminal");
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Hash, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub enum TerrainType {
+    Flat,
+    Hill,
+    Mont,
+    OFlat,
+}
+
+impl TerrainType {
+    pub fn new(height: f64, neighbor_heights: &[f64; 6]) -> TerrainType {
+        let is_peak = neighbor_heights.iter().all(|neighbor_height| *neighbor_height < height);
+        let steepest_slope = neighbor_heights.iter()
+            .map(|neighbor_height| (neighbor_height - height).abs())
+            .fold(0., f64::max);
+        match (is_peak, steepest_slope) {
+            (true, slope) if slope > 0.3 => TerrainType::Mont,
+            (_, slope) if slope > 0.6 => TerrainType::Mont,
+            (_, slope) if slope > 0.3 => TerrainType::Hill,
+            (_, slope) if slope > 0.1 => TerrainType::OFlat,
+            _ => TerrainType::Flat
+        }
+    }
+
+    /// Cost added to the biome cost for entering a hexagon of this terrain
+    pub fn movement_cost(&self) -> u32 {
+        match self {
+            TerrainType::Flat => 0,
+            TerrainType::OFlat => 1,
+            TerrainType::Hill => 3,
+            TerrainType::Mont => 8,
+        }
+    }
+}
+
+#[derive(Hash, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub enum BiomeType {
+    Snow,
+    WDeep,
+    WShallow,
+    // TODO make swamp texture greener
+    Swamp,
+    Boreal,
+    Temperate,
+    Warm,
+    Desert,
+    Stone,
+}
+
+impl BiomeType {
+    /// Flat color of the biome, for backends drawing without textures
+    pub fn color(&self) -> (u8, u8, u8) {
+        match self {
+            BiomeType::Snow => (235, 240, 245),
+            BiomeType::WDeep => (25, 55, 120),
+            BiomeType::WShallow => (55, 110, 180),
+            BiomeType::Swamp => (75, 95, 60),
+            BiomeType::Boreal => (40, 90, 70),
+            BiomeType::Temperate => (85, 150, 65),
+            BiomeType::Warm => (150, 160, 60),
+            BiomeType::Desert => (220, 195, 130),
+            BiomeType::Stone => (125, 120, 115),
+        }
+    }
+
+    /// Cost of entering a hexagon of this biome, deep water can only be crossed when the movement allows it
+    pub fn movement_cost(&self) -> u32 {
+        match self {
+            BiomeType::Temperate | BiomeType::Warm => 2,
+            BiomeType::Boreal | BiomeType::Desert => 3,
+            BiomeType::Snow | BiomeType::Stone => 4,
+            BiomeType::Swamp => 5,
+            BiomeType::WShallow => 6,
+            BiomeType::WDeep => 10,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use terrain::TerrainType;
+
+    /// Terrain of a hexagon at height 0 with one neighbor at the given height and the others level with it
+    fn terrain_next_to(neighbor_height: f64) -> TerrainType {
+        TerrainType::new(0., &[neighbor_height, 0., 0., 0., 0., 0.])
+    }
+
+    #[test]
+    fn peaks_are_mountains() {
+        assert_eq!(TerrainType::new(1., &[0.5; 6]), TerrainType::Mont);
+        // A low peak is only as rough as its slopes
+        assert_eq!(TerrainType::new(0.2, &[0.; 6]), TerrainType::OFlat);
+    }
+
+    #[test]
+    fn terrain_follows_the_steepest_slope() {
+        assert_eq!(terrain_next_to(0.7), TerrainType::Mont);
+        assert_eq!(terrain_next_to(-0.7), TerrainType::Mont);
+        assert_eq!(terrain_next_to(0.4), TerrainType::Hill);
+        assert_eq!(terrain_next_to(0.2), TerrainType::OFlat);
+        assert_eq!(terrain_next_to(0.05), TerrainType::Flat);
+        assert_eq!(terrain_next_to(0.), TerrainType::Flat);
+    }
+}
@@ -1,63 +1,25 @@
 use std::collections::HashMap;
 
-use rand::prelude::{IteratorRandom, StdRng};
-use rand::SeedableRng;
 use sdl2::image::LoadTexture;
 use sdl2::render::{Texture, TextureCreator};
-use sdl2::video::WindowContext;
 
+use terrain::{BiomeType, TerrainType};
 use tiles::Coordinates;
 
 const TEXTURES_BASE_DIR: &str = "assets/tiles/grid/hexset_grid_";
 
-#[derive(Hash, PartialEq, Eq, Clone, Debug)]
-pub enum TerrainType {
-    Flat,
-    Hill,
-    Mont,
-    OFlat,
-}
-
-#[derive(Hash, PartialEq, Eq, Clone, Debug)]
-pub enum BiomeType {
-    Snow,
-    WDeep,
-    WShallow,
-    // TODO make swamp texture greener
-    Swamp,
-    Boreal,
-    Temperate,
-    Warm,
-    Desert,
-    Stone,
-}
-
-impl BiomeType {
-    pub fn new(height: f64, humidity: f64) -> BiomeType {
-        match (height, humidity) {
-            (he, _hu) if he < -0.3 => BiomeType::WDeep,
-            (he, _hu) if he < 0. => BiomeType::WShallow,
-            (he, _hu) if he < 0.05 => BiomeType::Desert,
-            (he, _hu) if he > 2.5 => BiomeType::Snow,
-            (he, hu) if hu < -0.8 || he > 1.8 => BiomeType::Stone,
-            (_he, hu) if hu < -0.5 => BiomeType::Desert,
-            (_he, hu) if hu < 0. => BiomeType::Warm,
-            (_he, hu) if hu < 0.4 => BiomeType::Temperate,
-            (_he, hu) if hu < 0.8 => BiomeType::Boreal,
-            _ => BiomeType::Swamp
-        }
-    }
-}
-
-pub struct Textures<'a> {
-    texture_creator: &'a TextureCreator<WindowContext>,
+pub struct Textures<'a, C> {
+    seed: u32,
+    texture_creator: &'a TextureCreator<C>,
     textures_locations: HashMap<TerrainType, Vec<&'a str>>,
     biomes_locations: HashMap<BiomeType, &'a str>,
     textures_cache: HashMap<(TerrainType, BiomeType), Vec<Texture<'a>>>,
 }
 
-impl<'a> Textures<'a> {
-    pub fn new(texture_creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>) -> Textures<'a> {
+impl<'a, C> Textures<'a, C> {
+    const VARIANT_HASH_SALT: u64 = 1;
+
+    pub fn new(texture_creator: &'a TextureCreator<C>, seed: u32) -> Textures<'a, C> {
         let textures_locations = HashMap::from(
             [(TerrainType::Flat, Vec::from(["flat_01.png", "flat_02.png", "flat_03.png"])),
                 (TerrainType::Hill, Vec::from(["hill_01.png", "hill_02.png", "hill_03.png"])),
@@ -76,10 +38,10 @@ impl<'a> Textures<'a> {
                 (BiomeType::WShallow, "wshallow_"), ]
         );
 
-        Textures { texture_creator, textures_locations, textures_cache: Default::default(), biomes_locations }
+        Textures { seed, texture_creator, textures_locations, textures_cache: Default::default(), biomes_locations }
     }
 
-    pub fn random_texture(&mut self, texture_type: &(TerrainType, BiomeType), coordinates: &mut Coordinates) -> &Texture<'a> {
+    pub fn random_texture(&mut self, texture_type: &(TerrainType, BiomeType), coordinates: &Coordinates) -> &Texture<'a> {
         if !self.textures_cache.contains_key(texture_type) {
             let locations = self.textures_locations.get(&texture_type.0).expect("Missing texture type in textures locations");
             let loaded_textures = locations.iter().map(|location| {
@@ -94,9 +56,9 @@ impl<'a> Textures<'a> {
 
             self.textures_cache.insert(texture_type.clone(), loaded_textures);
         }
-        self.textures_cache.get(texture_type).expect("Unable to fetch texture")
-            .iter()
-            .choose(&mut StdRng::seed_from_u64(coordinates.quick_hash()))
+        let variants = self.textures_cache.get(texture_type).expect("Unable to fetch texture");
+        let variant = coordinates.seeded_hash(self.seed, Self::VARIANT_HASH_SALT) % variants.len() as u64;
+        variants.get(variant as usize)
             .expect("No texture associated with terrain")
     }
 }
\ No newline at end of file
@@ -0,0 +1,95 @@
+use terrain::{BiomeType, TerrainType};
+use tiles::{Coordinates, Hexagon};
+
+/// Color blended over a tile, the alpha giving its opacity
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Tint {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Tint {
+    pub const PLACEHOLDER: Tint = Tint { r: 60, g: 60, b: 60, a: 255 };
+    /// Darkens the hexagons seen before but not visible anymore
+    pub const EXPLORED: Tint = Tint { r: 0, g: 0, b: 0, a: 128 };
+    /// Highlights the hexagon picked with the mouse
+    pub const SELECTED: Tint = Tint { r: 255, g: 255, b: 255, a: 96 };
+
+    /// Blends the tint over an opaque color
+    pub fn blend(&self, (r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+        let mix = |below: u8, above: u8| ((below as u32 * (255 - self.a as u32) + above as u32 * self.a as u32) / 255) as u8;
+        (mix(r, self.r), mix(g, self.g), mix(b, self.b))
+    }
+}
+
+/// Backend drawing the hexagons of a `Grid`, one level after the other from the lowest
+pub trait TileRenderer {
+    fn clear(&mut self);
+
+    /// Draws the top of the hexagon at the given level, hexagons are drawn again for each level below their own
+    fn draw_tile(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, hexagon: &Hexagon, level: i32);
+
+    /// Darkens the tiles behind a hexagon raised to the given level
+    fn draw_shadow(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, level: i32);
+
+    /// Tints the top of the hexagon drawn at the given level
+    fn draw_overlay(&mut self, screen_center: &Coordinates, coordinates: &Coordinates, level: i32, tint: Tint);
+
+    /// Marks a hexagon that is not generated yet
+    fn draw_placeholder(&mut self, screen_center: &Coordinates, coordinates: &Coordinates) {
+        self.draw_overlay(screen_center, coordinates, 0, Tint::PLACEHOLDER);
+    }
+
+    fn present(&mut self);
+}
+
+/// Draw call received by a `RecordingRenderer`
+#[derive(Clone, PartialEq, Debug)]
+pub enum DrawCall {
+    Clear,
+    Tile { coordinates: Coordinates, texture_type: (TerrainType, BiomeType), level: i32 },
+    Shadow { coordinates: Coordinates, level: i32 },
+    Overlay { coordinates: Coordinates, level: i32, tint: Tint },
+    Placeholder { coordinates: Coordinates },
+    Present,
+}
+
+/// Keeps the draw calls instead of drawing, so the rendering logic can be checked without any display
+#[derive(Default, Debug)]
+pub struct RecordingRenderer {
+    pub calls: Vec<DrawCall>,
+}
+
+impl RecordingRenderer {
+    pub fn new() -> RecordingRenderer {
+        RecordingRenderer::default()
+    }
+}
+
+impl TileRenderer for RecordingRenderer {
+    fn clear(&mut self) {
+        self.calls.push(DrawCall::Clear);
+    }
+
+    fn draw_tile(&mut self, _screen_center: &Coordinates, coordinates: &Coordinates, hexagon: &Hexagon, level: i32) {
+        self.calls.push(DrawCall::Tile { coordinates: *coordinates, texture_type: hexagon.texture_type.clone(), level });
+    }
+
+    fn draw_shadow(&mut self, _screen_center: &Coordinates, coordinates: &Coordinates, level: i32) {
+        self.calls.push(DrawCall::Shadow { coordinates: *coordinates, level });
+    }
+
+    fn draw_overlay(&mut self, _screen_center: &Coordinates, coordinates: &Coordinates, level: i32, tint: Tint) {
+        self.calls.push(DrawCall::Overlay { coordinates: *coordinates, level, tint });
+    }
+
+    fn draw_placeholder(&mut self, _screen_center: &Coordinates, coordinates: &Coordinates) {
+        self.calls.push(DrawCall::Placeholder { coordinates: *coordinates });
+    }
+
+    fn present(&mut self) {
+        self.calls.push(DrawCall::Present);
+    }
+}
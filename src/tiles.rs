@@ -1,21 +1,45 @@
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
-use std::convert::TryInto;
-use std::fmt::Debug;
-use std::hash::{Hash, Hasher};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
 
-use ::{FLAT_SIDE_LENGTH, PIXEL_PER_HEXAGON};
+use serde::{Deserialize, Serialize};
+
+use ::{FLAT_SIDE_LENGTH, HEIGHT_SHIFT, PIXEL_PER_HEXAGON};
+use area::Area;
+use biomes::{BiomeRules, TileClimate};
+use chunks::{Chunk, ChunkCoordinates};
+use coastline::WaterDistanceMap;
 use generator::NoiseGenerator;
-use renderer::Printer;
-use textures::{BiomeType, TerrainType, Textures};
+use rivers::RiverMap;
+use terrain::{BiomeType, TerrainType};
+use tile_renderer::{TileRenderer, Tint};
+
+/// What is known of a hexagon under the fog of war
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Visibility {
+    Unseen,
+    /// Seen before but not from where the observer stands now
+    Explored,
+    Visible,
+}
 
 #[derive(Debug)]
 pub struct Hexagon {
     pub texture_type: (TerrainType, BiomeType),
-    pub height: u8,
+    /// Raw elevation from the noise, negative below the sea level
+    pub height: f64,
+    /// Elevation level the hexagon is rendered at, derived from the height
+    pub level: i32,
 }
 
-#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+impl Hexagon {
+    pub fn new(texture_type: (TerrainType, BiomeType), height: f64) -> Hexagon {
+        Hexagon { texture_type, height, level: (height + 0.4).floor() as i32 }
+    }
+}
+
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Coordinates {
     pub q: i32,
     pub r: i32,
@@ -23,20 +47,7 @@ pub struct Coordinates {
 
 impl Coordinates {
     const NEIGHBORS_PERMUTATIONS: [(i8, i8); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
-
-    // TODO make Area struct
-    pub fn build_hexagonal_area(center: Coordinates, radius: i32) -> Vec<Coordinates> {
-        let mut qr_vec = Vec::new();
-        for q in (center.q - radius)..=(center.q + radius) {
-            for r in (center.r - radius)..=(center.r + radius) {
-                let target_coordinates = Coordinates { q, r };
-                if center.distance_to(&target_coordinates) <= radius {
-                    qr_vec.push(target_coordinates);
-                }
-            }
-        }
-        qr_vec
-    }
+    const LINE_NUDGE: f64 = 1e-6;
 
     pub fn shift(&self, q_offset: i32, r_offset: i32) -> Coordinates {
         Coordinates { q: self.q + q_offset, r: self.r + r_offset }
@@ -44,23 +55,59 @@ impl Coordinates {
 
     pub fn neighbors(&self) -> [Coordinates; 6] {
         Coordinates::NEIGHBORS_PERMUTATIONS
-            .iter()
-            .map(|(q_permutation, r_permutation)| self.shift(*q_permutation as i32, *r_permutation as i32))
-            .collect::<Vec<Coordinates>>()
-            .try_into()
-            .unwrap()
+            .map(|(q_permutation, r_permutation)| self.shift(q_permutation as i32, r_permutation as i32))
+    }
+
+    /// Hexagons exactly `radius` hexagons away, going around this one from the bottom left corner
+    pub fn ring(&self, radius: i32) -> Ring {
+        Ring { current: self.shift(-radius, radius), radius, side: if radius < 0 { 6 } else { 0 }, step: 0 }
+    }
+
+    /// Hexagons at most `radius` hexagons away, this one first then ring by ring
+    pub fn spiral(&self, radius: i32) -> Spiral {
+        // Negative radiuses start with an empty ring, and stop right away
+        Spiral { center: *self, radius, ring: self.ring(radius.min(0)) }
     }
 
     pub fn as_offset(&self, center: &Coordinates) -> (i32, i32) {
         let normalized_q = (self.q - center.q) as f32;
         let normalized_r = (self.r - center.r) as f32;
+        let (x_unit, y_unit) = Coordinates::offset_units();
 
-        let x_f32 = (PIXEL_PER_HEXAGON as f32 * FLAT_SIDE_LENGTH).round() * (2. * normalized_q + normalized_r);
-        let y_f32 = (28. / 30.) * PIXEL_PER_HEXAGON as f32 * 1.5 * normalized_r as f32;
+        let x_f32 = x_unit * (2. * normalized_q + normalized_r);
+        let y_f32 = y_unit * normalized_r;
 
         (x_f32.round() as i32, y_f32.round() as i32)
     }
 
+    /// Hexagon under the pixel at the given offset from the center, inverse of `as_offset`
+    pub fn from_offset((x, y): (i32, i32), center: &Coordinates) -> Coordinates {
+        let (x_unit, y_unit) = Coordinates::offset_units();
+        let r = y as f64 / y_unit as f64;
+        let q = (x as f64 / x_unit as f64 - r) / 2.;
+        Coordinates::round(q, r).shift(center.q, center.r)
+    }
+
+    /// Position of the hexagon center in the world, independent from how it is rendered.
+    /// Neighbor centers are one unit apart.
+    pub fn world_position(&self) -> (f64, f64) {
+        let x = self.q as f64 + self.r as f64 / 2.;
+        let y = self.r as f64 * 3f64.sqrt() / 2.;
+        (x, y)
+    }
+
+    /// Hexagons crossed by the straight line between the centers of both hexagons, from this one to the other included
+    pub fn line_to(&self, to: &Coordinates) -> Vec<Coordinates> {
+        let distance = self.distance_to(to);
+        // Both ends are nudged the same way, so lines running along the edges between hexagons always pick the same side
+        let (from_q, from_r) = (self.q as f64 + Coordinates::LINE_NUDGE, self.r as f64 + Coordinates::LINE_NUDGE);
+        let (to_q, to_r) = (to.q as f64 + Coordinates::LINE_NUDGE, to.r as f64 + Coordinates::LINE_NUDGE);
+        (0..=distance)
+            .map(|step| if distance == 0 { 0. } else { step as f64 / distance as f64 })
+            .map(|t| Coordinates::round(from_q + (to_q - from_q) * t, from_r + (to_r - from_r) * t))
+            .collect()
+    }
+
     pub fn distance_to(&self, to: &Coordinates) -> i32 {
         let vec_distance = [self.q - to.q, self.r - to.r, self.s() - to.s()];
         let vec: Vec<i32> = vec_distance.iter().map(|value| value.abs()).collect();
@@ -68,80 +115,820 @@ impl Coordinates {
         result / 2
     }
 
-    pub fn quick_hash(&self) -> u64 {
-        let mut s = DefaultHasher::new();
-        self.hash(&mut s);
-        s.finish()
+    /// Stable hash of the hexagon in the world of the given seed, used for all per tile randomness.
+    ///
+    /// The seed, q, r and salt are mixed one after the other with the SplitMix64 finalizer, so the output
+    /// never depends on the platform or the Rust version. Each use of the hash (texture variants,
+    /// decorations, spawns...) has its own salt so their random choices are independent.
+    pub fn seeded_hash(&self, seed: u32, salt: u64) -> u64 {
+        [seed as u64, self.q as u32 as u64, self.r as u32 as u64, salt]
+            .iter()
+            .fold(0, |hash, value| Coordinates::split_mix(hash ^ value))
     }
 
     fn s(&self) -> i32 {
         -self.q - self.r
     }
+
+    /// Pixels per unit of 2q + r horizontally, and per row vertically
+    fn offset_units() -> (f32, f32) {
+        ((PIXEL_PER_HEXAGON as f32 * FLAT_SIDE_LENGTH).round(), (28. / 30.) * PIXEL_PER_HEXAGON as f32 * 1.5)
+    }
+
+    /// Hexagon containing the fractional coordinates, the cube coordinate furthest from its rounded value
+    /// is recomputed from the two others so they still sum to 0
+    fn round(q: f64, r: f64) -> Coordinates {
+        let s = -q - r;
+        let (mut rounded_q, mut rounded_r, rounded_s) = (q.round(), r.round(), s.round());
+        let (q_difference, r_difference, s_difference) = ((rounded_q - q).abs(), (rounded_r - r).abs(), (rounded_s - s).abs());
+        if q_difference > r_difference && q_difference > s_difference {
+            rounded_q = -rounded_r - rounded_s;
+        } else if r_difference > s_difference {
+            rounded_r = -rounded_q - rounded_s;
+        }
+        Coordinates { q: rounded_q as i32, r: rounded_r as i32 }
+    }
+
+    fn neighbor(&self, direction: usize) -> Coordinates {
+        let (q_permutation, r_permutation) = Coordinates::NEIGHBORS_PERMUTATIONS[direction];
+        self.shift(q_permutation as i32, r_permutation as i32)
+    }
+
+    fn split_mix(value: u64) -> u64 {
+        let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Iterator over the hexagons of a ring, see `Coordinates::ring`
+#[derive(Clone, Debug)]
+pub struct Ring {
+    current: Coordinates,
+    radius: i32,
+    /// Direction of the side being walked along, 6 once the ring is done
+    side: usize,
+    /// Hexagons already walked along the current side
+    step: i32,
+}
+
+impl Iterator for Ring {
+    type Item = Coordinates;
+
+    fn next(&mut self) -> Option<Coordinates> {
+        if self.side >= 6 {
+            return None;
+        }
+        let coordinates = self.current;
+        if self.radius == 0 {
+            self.side = 6;
+            return Some(coordinates);
+        }
+        self.current = self.current.neighbor(self.side);
+        self.step += 1;
+        if self.step == self.radius {
+            self.side += 1;
+            self.step = 0;
+        }
+        Some(coordinates)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = match self.side {
+            6 => 0,
+            _ if self.radius == 0 => 1,
+            side => ((6 - side as i32) * self.radius - self.step) as usize,
+        };
+        (left, Some(left))
+    }
+}
+
+/// Iterator over the hexagons of a hexagonal area, see `Coordinates::spiral`
+#[derive(Clone, Debug)]
+pub struct Spiral {
+    center: Coordinates,
+    radius: i32,
+    ring: Ring,
+}
+
+impl Iterator for Spiral {
+    type Item = Coordinates;
+
+    fn next(&mut self) -> Option<Coordinates> {
+        loop {
+            if let Some(coordinates) = self.ring.next() {
+                return Some(coordinates);
+            }
+            if self.ring.radius >= self.radius {
+                return None;
+            }
+            self.ring = self.center.ring(self.ring.radius + 1);
+        }
+    }
+}
+
+impl Display for Coordinates {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.q, self.r)
+    }
 }
 
+impl FromStr for Coordinates {
+    type Err = String;
+
+    /// Parses coordinates formatted as `q,r`
+    fn from_str(s: &str) -> Result<Coordinates, String> {
+        let mut values = s.split(',')
+            .map(|value| value.trim().parse::<i32>().map_err(|e| format!("Invalid coordinates {}: {}", s, e)));
+        match (values.next(), values.next(), values.next()) {
+            (Some(q), Some(r), None) => Ok(Coordinates { q: q?, r: r? }),
+            _ => Err(format!("Invalid coordinates {}: expected q,r", s))
+        }
+    }
+}
+
+/// Hexagons generated so far, stored by chunks.
+///
+/// Chunks are generated on demand and kept once visited, the least recently used ones being dropped
+/// when the memory budget is exceeded. Chunks the grid was built from are never dropped.
 pub struct Grid {
-    pub hexagons: HashMap<Coordinates, Hexagon>,
+    chunks: HashMap<ChunkCoordinates, Chunk>,
+    /// Chunks of the hexagons the grid was built from, loaded hexagons cannot be generated again
+    pinned: HashSet<ChunkCoordinates>,
+    /// Chunks covering the area of the last `touch`, still in use
+    current: HashSet<ChunkCoordinates>,
+    /// Maximum memory used by the chunks, in bytes, the chunks of the current area are always kept
+    memory_budget: usize,
+    /// Incremented each time the area changes, to find the least recently used chunks
+    tick: u64,
+    /// Whether only the hexagons seen so far are drawn
+    fog_of_war: bool,
+    /// Hexagons marked as visible by the last `reveal`
+    visible: Area,
+    /// Every hexagon seen so far, visible ones included, kept apart from the chunks so dropping them forgets nothing
+    explored: HashSet<Coordinates>,
+    /// Hexagon highlighted when drawing
+    selected: Option<Coordinates>,
 }
 
 impl Grid {
-    pub fn new(noise_generator: &NoiseGenerator, area: &[Coordinates]) -> Result<Grid, &'static str> {
-        // TODO edge detection, so tiles are aware of their neighbors
-        // -> make peaks at the top
-        // -> make deserts for beach on low altitudes only if close to water or sand
+    pub const DEFAULT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+    /// Height of the eyes above the surface, so gentle slopes do not block the view
+    const EYE_HEIGHT: f64 = 0.2;
+
+    pub fn new(noise_generator: &NoiseGenerator, biome_rules: &BiomeRules, area: &Area) -> Result<Grid, &'static str> {
         // TODO generate hex based random elements according to biome (cactuses, trees...)
-        let hexagons = area.iter()
-            .map(|coordinates| {
-                let height = noise_generator.height(coordinates);
-                let humidity = noise_generator.humidity(coordinates);
-                (*coordinates, Hexagon { texture_type: (TerrainType::Flat, BiomeType::new(height, humidity)), height: (height + 0.4).floor() as u8 })
-            })
-            .collect();
+        let mut grid = Grid::from_hexagons(Vec::new());
+        grid.at(noise_generator, biome_rules, area);
+
+        Ok(grid)
+    }
+
+    /// Grid of already generated hexagons, missing ones are generated when their area is requested
+    pub fn from_hexagons<I: IntoIterator<Item=(Coordinates, Hexagon)>>(hexagons: I) -> Grid {
+        let mut grid = Grid {
+            chunks: HashMap::new(),
+            pinned: HashSet::new(),
+            current: HashSet::new(),
+            memory_budget: Grid::DEFAULT_MEMORY_BUDGET,
+            tick: 0,
+            fog_of_war: false,
+            visible: Area::default(),
+            explored: HashSet::new(),
+            selected: None,
+        };
+        for (coordinates, hexagon) in hexagons {
+            grid.insert(&coordinates, hexagon);
+        }
+        grid.pinned = grid.chunks.keys().cloned().collect();
+        grid
+    }
+
+    pub fn get(&self, coordinates: &Coordinates) -> Option<&Hexagon> {
+        self.chunks.get(&ChunkCoordinates::of(coordinates))
+            .and_then(|chunk| chunk.get(coordinates))
+    }
+
+    /// Every stored hexagon, in no particular order
+    pub fn hexagons(&self) -> impl Iterator<Item=(Coordinates, &Hexagon)> {
+        self.chunks.iter()
+            .flat_map(|(chunk_coordinates, chunk)| chunk.hexagons(chunk_coordinates))
+    }
+
+    pub fn set_memory_budget(&mut self, memory_budget: usize) {
+        self.memory_budget = memory_budget;
+        self.evict();
+    }
+
+    /// Drops every hexagon, for instance when the generation parameters change
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.pinned.clear();
+        self.current.clear();
+        self.visible = Area::default();
+        self.explored.clear();
+    }
 
-        Ok(Grid { hexagons })
+    pub fn set_selected(&mut self, selected: Option<Coordinates>) {
+        self.selected = selected;
     }
 
-    pub fn at(&mut self, noise_generator: &NoiseGenerator, area: &[Coordinates]) {
-        // TODO move origin and related transformations into noise_generator ?
-        let hexagons = area.iter()
-            .map(|coordinates| (*coordinates, self.hexagons.remove(coordinates).unwrap_or_else(|| {
-                let height = noise_generator.height(coordinates);
-                let humidity = noise_generator.humidity(coordinates);
-                Hexagon { texture_type: (TerrainType::Flat, BiomeType::new(height, humidity)), height: (height + 0.4).floor() as u8 }
-            })))
+    pub fn visibility(&self, coordinates: &Coordinates) -> Visibility {
+        if self.visible.contains(coordinates) {
+            Visibility::Visible
+        } else if self.explored.contains(coordinates) {
+            Visibility::Explored
+        } else {
+            Visibility::Unseen
+        }
+    }
+
+    /// Every hexagon seen so far, in no particular order
+    pub fn explored(&self) -> impl Iterator<Item=&Coordinates> {
+        self.explored.iter()
+    }
+
+    /// Marks hexagons as seen before, for instance when loading a world
+    pub fn explore<I: IntoIterator<Item=Coordinates>>(&mut self, coordinates: I) {
+        self.explored.extend(coordinates);
+    }
+
+    /// Hides the hexagons never seen when drawing, and darkens those not visible anymore
+    pub fn set_fog_of_war(&mut self, fog_of_war: bool) {
+        self.fog_of_war = fog_of_war;
+    }
+
+    /// Makes sure the chunks covering the area are generated, generating their missing hexagons
+    pub fn at(&mut self, noise_generator: &NoiseGenerator, biome_rules: &BiomeRules, area: &Area) {
+        // Missing hexagons are generated together, so their surroundings are sampled only once
+        let missing_area: Area = self.missing_chunks(area)
+            .into_iter()
+            .flat_map(|(_, missing)| missing)
             .collect();
-        self.hexagons = hexagons;
+        for (coordinates, hexagon) in Grid::generate_hexagons(noise_generator, biome_rules, &missing_area) {
+            self.insert(&coordinates, hexagon);
+        }
+        self.touch(area);
+    }
+
+    /// Chunks covering the area that are not fully generated, with their missing hexagons
+    pub fn missing_chunks(&self, area: &Area) -> Vec<(ChunkCoordinates, Area)> {
+        let needed_chunks: HashSet<ChunkCoordinates> = area.iter().map(ChunkCoordinates::of).collect();
+        needed_chunks.into_iter()
+            .map(|chunk_coordinates| match self.chunks.get(&chunk_coordinates) {
+                Some(chunk) => (chunk_coordinates, chunk.missing(&chunk_coordinates)),
+                None => (chunk_coordinates, chunk_coordinates.area()),
+            })
+            .filter(|(_, missing)| !missing.is_empty())
+            .collect()
     }
 
-    pub fn draw(&self, printer: &mut Printer, center: Coordinates, textures: &mut Textures, radius: i32) {
-        printer.clear();
-        for elevation in 0..=4 {
-            if elevation > 0 {
-                self.hexagons
+    pub fn insert(&mut self, coordinates: &Coordinates, hexagon: Hexagon) {
+        let chunk = self.chunks.entry(ChunkCoordinates::of(coordinates)).or_default();
+        chunk.insert(coordinates, hexagon);
+        chunk.last_used = self.tick;
+    }
+
+    /// Marks the chunks covering the area as the most recently used, dropping the least recently used beyond the budget
+    pub fn touch(&mut self, area: &Area) {
+        self.tick += 1;
+        self.current = area.iter().map(ChunkCoordinates::of).collect();
+        for chunk_coordinates in self.current.iter() {
+            if let Some(chunk) = self.chunks.get_mut(chunk_coordinates) {
+                chunk.last_used = self.tick;
+            }
+        }
+        self.evict();
+    }
+
+    /// Draws the hexagons of the area around the screen center, from the back rows to the front ones
+    pub fn draw<R: TileRenderer>(&self, renderer: &mut R, center: Coordinates, area: &Area) {
+        renderer.clear();
+        // Hexagons still being generated are drawn as placeholders, below everything else, unless hidden by the fog
+        if !self.fog_of_war {
+            area.iter()
+                .filter(|coordinates| self.get(coordinates).is_none())
+                .for_each(|coordinates| renderer.draw_placeholder(&center, coordinates));
+        }
+        let mut drawn: Vec<(Coordinates, &Hexagon)> = area.iter()
+            .filter_map(|coordinates| self.get(coordinates).map(|hexagon| (*coordinates, hexagon)))
+            .filter(|(coordinates, _)| self.is_drawn(coordinates))
+            .collect();
+        // Areas can come in any order, rings and spirals included, so the rows are sorted here rather than relied upon
+        drawn.sort_by_key(|(coordinates, _)| (coordinates.r, -coordinates.q));
+        let lowest_level = drawn.iter().map(|(_, hexagon)| hexagon.level).min().unwrap_or(0);
+        let highest_level = drawn.iter().map(|(_, hexagon)| hexagon.level).max().unwrap_or(0);
+        for level in lowest_level..=highest_level {
+            if level > lowest_level {
+                drawn
                     .iter()
-                    .filter(|(_, hexagon)| hexagon.height == elevation)
+                    .filter(|(_, hexagon)| hexagon.level == level)
                     .for_each(|(coordinates, _)| {
-                        printer.print_shadow(&center, coordinates, elevation);
+                        renderer.draw_shadow(&center, coordinates, level);
                     });
             }
 
             // TODO printing only the upper layer looks nice, but we can have holes if the tile below is too low !
-            for r in (center.r - radius)..=(center.r + radius) {
-                for minus_q in (-center.q - radius)..=(-center.q + radius) {
-                    match Option::Some(Coordinates { q: -minus_q, r })
-                        .filter(|coordinates| center.distance_to(coordinates) <= radius)
-                        .map(|coordinates| self.hexagons.get(&coordinates)
-                            .map(|hexagon| (coordinates, hexagon)))
-                        .flatten()
-                        .filter(|(_, hexagon)| hexagon.height >= elevation) {
-                        None => {}
-                        Some((mut coordinates, hexagon)) => {
-                            let texture = textures.random_texture(&hexagon.texture_type, &mut coordinates);
-                            printer.print_texture(&center, &coordinates, texture, elevation);
-                        }
+            drawn
+                .iter()
+                .filter(|(_, hexagon)| hexagon.level >= level)
+                .for_each(|(coordinates, hexagon)| {
+                    renderer.draw_tile(&center, coordinates, hexagon, level);
+                    if self.fog_of_war && hexagon.level == level && self.visibility(coordinates) == Visibility::Explored {
+                        renderer.draw_overlay(&center, coordinates, level, Tint::EXPLORED);
+                    }
+                    if self.selected == Some(*coordinates) && hexagon.level == level {
+                        renderer.draw_overlay(&center, coordinates, level, Tint::SELECTED);
                     }
-                }
+                });
+        }
+        renderer.present();
+    }
+
+    /// Whether the surface of the target can be seen from the eyes of someone standing on the origin.
+    ///
+    /// The view is blocked by any hexagon of the line between them rising above the sight line, water hexagons
+    /// standing at the sea level. Nothing can be seen through hexagons that are not generated yet.
+    pub fn line_of_sight(&self, from: &Coordinates, to: &Coordinates) -> bool {
+        let surface = |coordinates: &Coordinates| self.get(coordinates).map(|hexagon| hexagon.height.max(0.));
+        let (eyes, target) = match (surface(from), surface(to)) {
+            (Some(from_surface), Some(to_surface)) => (from_surface + Grid::EYE_HEIGHT, to_surface),
+            _ => return false,
+        };
+        let line = from.line_to(to);
+        let steps = (line.len() - 1) as f64;
+        line.iter()
+            .enumerate()
+            .take(line.len() - 1)
+            .skip(1)
+            .all(|(step, coordinates)| {
+                let sight = eyes + (target - eyes) * step as f64 / steps;
+                surface(coordinates).is_some_and(|height| height <= sight)
+            })
+    }
+
+    /// Hexagons at most `radius` hexagons away from the observer that can be seen from it
+    pub fn field_of_view(&self, observer: &Coordinates, radius: i32) -> Area {
+        observer.spiral(radius)
+            .filter(|coordinates| self.line_of_sight(observer, coordinates))
+            .collect()
+    }
+
+    /// Marks the field of view of the observer as visible, the hexagons visible before and out of it become explored
+    pub fn reveal(&mut self, observer: &Coordinates, radius: i32) {
+        let visible = self.field_of_view(observer, radius);
+        self.explored.extend(visible.iter());
+        self.visible = visible;
+    }
+
+    /// Hexagon of the area seen at the given offset from the screen center, as drawn by `draw`.
+    ///
+    /// Elevated hexagons are drawn shifted up, over the hexagons behind them, so the levels are tried from the highest:
+    /// the first hexagon found under the offset shifted back down to its level is the one drawn last.
+    /// Hexagons still being generated or hidden by the fog cannot be picked.
+    pub fn pick(&self, center: Coordinates, area: &Area, (x, y): (i32, i32)) -> Option<Coordinates> {
+        let levels: Vec<i32> = area.iter()
+            .filter(|coordinates| self.is_drawn(coordinates))
+            .filter_map(|coordinates| self.get(coordinates))
+            .map(|hexagon| hexagon.level)
+            .collect();
+        let lowest_level = *levels.iter().min()?;
+        let highest_level = *levels.iter().max()?;
+        (lowest_level..=highest_level).rev().find_map(|level| {
+            let coordinates = Coordinates::from_offset((x, y - level * HEIGHT_SHIFT), &center);
+            self.get(&coordinates)
+                .filter(|hexagon| area.contains(&coordinates) && self.is_drawn(&coordinates) && hexagon.level >= level)
+                .map(|_| coordinates)
+        })
+    }
+
+    /// Whether the hexagon is drawn at all, the fog of war hiding the ones never seen
+    fn is_drawn(&self, coordinates: &Coordinates) -> bool {
+        !self.fog_of_war || self.explored.contains(coordinates)
+    }
+
+    /// Drops the least recently used chunks until the memory budget is met, except the current and pinned ones
+    fn evict(&mut self) {
+        while self.chunks.len() * Chunk::MEMORY > self.memory_budget {
+            let least_recently_used = self.chunks.iter()
+                .filter(|(chunk_coordinates, _)| !self.current.contains(chunk_coordinates) && !self.pinned.contains(chunk_coordinates))
+                .min_by_key(|(_, chunk)| chunk.last_used)
+                .map(|(chunk_coordinates, _)| *chunk_coordinates);
+            match least_recently_used {
+                Some(chunk_coordinates) => { self.chunks.remove(&chunk_coordinates); }
+                None => break,
             }
         }
-        printer.present();
+    }
+
+    pub fn generate_hexagons(noise_generator: &NoiseGenerator, biome_rules: &BiomeRules, area: &Area) -> HashMap<Coordinates, Hexagon> {
+        // Heights are sampled once for the rivers, which reach the furthest, the water distances and the outer neighbors
+        let expanded_area = area.expand(RiverMap::CATCHMENT_RADIUS + 1);
+        let heights: HashMap<Coordinates, f64> = expanded_area.iter()
+            .cloned()
+            .zip(noise_generator.heights(expanded_area.coordinates()))
+            .collect();
+        let river_map = RiverMap::new(area, &heights);
+        let water_distance_map = WaterDistanceMap::new(&heights);
+        let area_heights: Vec<f64> = area.iter().map(|coordinates| heights[coordinates]).collect();
+        let samples = noise_generator.sample_with_heights(area.coordinates(), &area_heights);
+
+        area.iter()
+            .zip(samples)
+            .map(|(coordinates, sample)| {
+                let climate = TileClimate {
+                    height: sample.height,
+                    humidity: sample.humidity,
+                    temperature: sample.temperature,
+                    water_distance: water_distance_map.distance(coordinates),
+                };
+                let (texture_type, height) = match river_map.river_biome(coordinates) {
+                    // Rivers are carved down to the sea level, so their water is never drawn or climbed above the land
+                    Some(river_biome) => ((TerrainType::Flat, river_biome), sample.height.min(0.)),
+                    None => {
+                        let neighbor_heights = coordinates.neighbors().map(|neighbor| heights[&neighbor]);
+                        ((TerrainType::new(sample.height, &neighbor_heights), biome_rules.classify(&climate)), sample.height)
+                    }
+                };
+                (*coordinates, Hexagon::new(texture_type, height))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use area::Area;
+    use biomes::BiomeRules;
+    use chunks::Chunk;
+    use generator::{GeneratorParameters, NoiseGenerator};
+    use noise_graph::NoiseGraph;
+    use terrain::{BiomeType, TerrainType};
+    use tile_renderer::{DrawCall, RecordingRenderer, Tint};
+    use tiles::{Coordinates, Grid, Hexagon, Visibility};
+    use HEIGHT_SHIFT;
+
+    const ORIGIN: Coordinates = Coordinates { q: 0, r: 0 };
+
+    /// Temperate hexagons covering the area at the sea level
+    fn flat_hexagons(area: &Area) -> Vec<(Coordinates, Hexagon)> {
+        area.iter()
+            .map(|coordinates| (*coordinates, Hexagon::new((TerrainType::Flat, BiomeType::Temperate), 0.)))
+            .collect()
+    }
+
+    /// Flat grid around the origin with two raised hexagons and two missing ones
+    fn draw_test_grid() -> (Vec<Coordinates>, Vec<DrawCall>) {
+        let missing = vec![Coordinates { q: 1, r: 0 }, Coordinates { q: -2, r: 2 }];
+        let raised = [ORIGIN, Coordinates { q: 0, r: 1 }];
+        let area = Area::hexagon(ORIGIN, 2);
+        let mut grid = Grid::from_hexagons(flat_hexagons(&area).into_iter()
+            .filter(|(coordinates, _)| !missing.contains(coordinates)));
+        for coordinates in raised.iter() {
+            grid.insert(coordinates, Hexagon::new((TerrainType::Flat, BiomeType::Temperate), 1.));
+        }
+
+        let mut renderer = RecordingRenderer::new();
+        grid.draw(&mut renderer, ORIGIN, &area);
+        (missing, renderer.calls)
+    }
+
+    #[test]
+    fn draws_rows_from_back_to_front() {
+        let (_, calls) = draw_test_grid();
+        for level in 0..=1 {
+            let rows: Vec<i32> = calls.iter()
+                .filter_map(|call| match call {
+                    DrawCall::Tile { coordinates, level: tile_level, .. } if *tile_level == level => Some(coordinates.r),
+                    _ => None,
+                })
+                .collect();
+            assert!(!rows.is_empty());
+            assert!(rows.windows(2).all(|pair| pair[0] <= pair[1]), "rows {:?} at level {}", rows, level);
+        }
+    }
+
+    #[test]
+    fn draws_shadows_only_above_the_lowest_level() {
+        let (_, calls) = draw_test_grid();
+        let mut shadows: Vec<(Coordinates, i32)> = calls.iter()
+            .filter_map(|call| match call {
+                DrawCall::Shadow { coordinates, level } => Some((*coordinates, *level)),
+                _ => None,
+            })
+            .collect();
+        shadows.sort_by_key(|(coordinates, _)| coordinates.r);
+        assert_eq!(shadows, vec![(ORIGIN, 1), (Coordinates { q: 0, r: 1 }, 1)]);
+    }
+
+    #[test]
+    fn draws_a_placeholder_for_each_missing_hexagon() {
+        let (missing, calls) = draw_test_grid();
+        let placeholders: Vec<Coordinates> = calls.iter()
+            .filter_map(|call| match call {
+                DrawCall::Placeholder { coordinates } => Some(*coordinates),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(placeholders, missing);
+        assert!(calls.iter().all(|call| match call {
+            DrawCall::Tile { coordinates, .. } => !missing.contains(coordinates),
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn keeps_the_hexagons_it_was_built_from() {
+        let mut grid = Grid::from_hexagons(flat_hexagons(&Area::hexagon(ORIGIN, 0)));
+        grid.set_memory_budget(0);
+        assert!(grid.get(&ORIGIN).is_some());
+
+        let far_away = Coordinates { q: 10 * Chunk::SIZE, r: 0 };
+        for (coordinates, hexagon) in flat_hexagons(&Area::hexagon(far_away, 0)) {
+            grid.insert(&coordinates, hexagon);
+        }
+        grid.touch(&Area::hexagon(ORIGIN, 0));
+        assert!(grid.get(&ORIGIN).is_some());
+        assert!(grid.get(&far_away).is_none());
+    }
+
+    #[test]
+    fn offsets_go_back_to_their_hexagon() {
+        let center = Coordinates { q: -4, r: 7 };
+        for coordinates in Area::hexagon(center, 10).iter() {
+            assert_eq!(Coordinates::from_offset(coordinates.as_offset(&center), &center), *coordinates);
+        }
+    }
+
+    #[test]
+    fn picks_the_raised_hexagon_in_front() {
+        let front = Coordinates { q: 0, r: 1 };
+        let area = Area::hexagon(ORIGIN, 2);
+        let mut grid = Grid::from_hexagons(flat_hexagons(&area));
+        let behind = (4, 0);
+        assert_eq!(grid.pick(ORIGIN, &area, behind), Some(ORIGIN));
+
+        // The front hexagon, raised by two levels, now covers the hexagon behind it
+        grid.insert(&front, Hexagon::new((TerrainType::Mont, BiomeType::Stone), 2.));
+        let (x, y) = front.as_offset(&ORIGIN);
+        assert_eq!(grid.pick(ORIGIN, &area, (x, y + 2 * HEIGHT_SHIFT)), Some(front));
+        assert_eq!(grid.pick(ORIGIN, &area, behind), Some(front));
+        assert_eq!(grid.pick(ORIGIN, &area, (x, y)), Some(front));
+    }
+
+    #[test]
+    fn highlights_the_selected_hexagon() {
+        let area = Area::hexagon(ORIGIN, 1);
+        let mut grid = Grid::from_hexagons(flat_hexagons(&area));
+        grid.set_selected(grid.pick(ORIGIN, &area, (0, 0)));
+
+        let mut renderer = RecordingRenderer::new();
+        grid.draw(&mut renderer, ORIGIN, &area);
+        let selected = DrawCall::Overlay { coordinates: ORIGIN, level: 0, tint: Tint::SELECTED };
+        let position = renderer.calls.iter().position(|call| *call == selected).expect("Selection not drawn");
+        assert!(matches!(renderer.calls[position - 1], DrawCall::Tile { coordinates: ORIGIN, .. }));
+    }
+
+    #[test]
+    fn lines_join_both_ends_through_neighbors() {
+        for to in Area::hexagon(ORIGIN, 6).iter() {
+            let line = ORIGIN.line_to(to);
+            assert_eq!(line.len() as i32, ORIGIN.distance_to(to) + 1);
+            assert_eq!(line.first(), Some(&ORIGIN));
+            assert_eq!(line.last(), Some(to));
+            assert!(line.windows(2).all(|pair| pair[0].distance_to(&pair[1]) == 1), "line {:?}", line);
+        }
+    }
+
+    #[test]
+    fn lines_along_edges_pick_the_same_side() {
+        // The middle of this line is on the edge between (1, 0) and (0, 1)
+        let to = Coordinates { q: 1, r: 1 };
+        let line = ORIGIN.line_to(&to);
+        assert_eq!(line, vec![ORIGIN, Coordinates { q: 1, r: 0 }, to]);
+        let mut back = to.line_to(&ORIGIN);
+        back.reverse();
+        assert_eq!(back, line);
+    }
+
+    #[test]
+    fn short_lines() {
+        assert_eq!(ORIGIN.line_to(&ORIGIN), vec![ORIGIN]);
+        for neighbor in ORIGIN.neighbors().iter() {
+            assert_eq!(ORIGIN.line_to(neighbor), vec![ORIGIN, *neighbor]);
+        }
+    }
+
+    #[test]
+    fn tall_hexagons_block_the_sight() {
+        let target = Coordinates { q: 4, r: 0 };
+        let middle = Coordinates { q: 2, r: 0 };
+        let grid_with_middle = |height: f64| {
+            let mut grid = Grid::from_hexagons(flat_hexagons(&Area::hexagon(ORIGIN, 4)));
+            grid.insert(&middle, Hexagon::new((TerrainType::Flat, BiomeType::Temperate), height));
+            grid
+        };
+        assert!(grid_with_middle(0.05).line_of_sight(&ORIGIN, &target));
+        assert!(!grid_with_middle(2.).line_of_sight(&ORIGIN, &target));
+        assert!(!grid_with_middle(2.).line_of_sight(&target, &ORIGIN));
+    }
+
+    #[test]
+    fn fog_of_war_hides_unseen_and_darkens_explored_hexagons() {
+        let area = Area::hexagon(ORIGIN, 6);
+        let mut grid = Grid::from_hexagons(flat_hexagons(&area));
+        grid.set_fog_of_war(true);
+        let draw = |grid: &Grid| {
+            let mut renderer = RecordingRenderer::new();
+            grid.draw(&mut renderer, ORIGIN, &area);
+            renderer.calls
+        };
+        let tiles = |calls: &[DrawCall]| -> Area {
+            calls.iter()
+                .filter_map(|call| match call {
+                    DrawCall::Tile { coordinates, .. } => Some(*coordinates),
+                    _ => None,
+                })
+                .collect()
+        };
+        let overlays = |calls: &[DrawCall]| -> Vec<Coordinates> {
+            calls.iter()
+                .filter_map(|call| match call {
+                    DrawCall::Overlay { coordinates, tint, .. } if *tint == Tint::EXPLORED => Some(*coordinates),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        assert_eq!(grid.visibility(&ORIGIN), Visibility::Unseen);
+        assert_eq!(draw(&grid), vec![DrawCall::Clear, DrawCall::Present]);
+
+        grid.reveal(&ORIGIN, 1);
+        let first_view = Area::hexagon(ORIGIN, 1);
+        assert!(first_view.iter().all(|coordinates| grid.visibility(coordinates) == Visibility::Visible));
+        let calls = draw(&grid);
+        assert_eq!(tiles(&calls).len(), 7);
+        assert!(first_view.iter().all(|coordinates| tiles(&calls).contains(coordinates)));
+        assert!(overlays(&calls).is_empty());
+
+        let moved = Coordinates { q: 4, r: 0 };
+        grid.reveal(&moved, 1);
+        assert!(first_view.iter().all(|coordinates| grid.visibility(coordinates) == Visibility::Explored));
+        assert_eq!(grid.visibility(&moved), Visibility::Visible);
+        assert_eq!(grid.visibility(&Coordinates { q: -4, r: 0 }), Visibility::Unseen);
+        let calls = draw(&grid);
+        assert_eq!(tiles(&calls).len(), 14);
+        let mut darkened = overlays(&calls);
+        darkened.sort_by_key(|coordinates| (coordinates.r, coordinates.q));
+        let mut expected: Vec<Coordinates> = first_view.into_iter().collect();
+        expected.sort_by_key(|coordinates| (coordinates.r, coordinates.q));
+        assert_eq!(darkened, expected);
+        // Each overlay darkens the tile drawn just before it
+        for (index, call) in calls.iter().enumerate() {
+            if let DrawCall::Overlay { coordinates, .. } = call {
+                assert!(matches!(&calls[index - 1], DrawCall::Tile { coordinates: tile, .. } if tile == coordinates));
+            }
+        }
+    }
+
+    #[test]
+    fn explored_hexagons_survive_dropped_chunks() {
+        let area = Area::hexagon(ORIGIN, 2);
+        let mut grid = Grid::from_hexagons(Vec::new());
+        for (coordinates, hexagon) in flat_hexagons(&area) {
+            grid.insert(&coordinates, hexagon);
+        }
+        grid.reveal(&ORIGIN, 1);
+        grid.reveal(&ORIGIN.shift(1, 0), 0);
+
+        grid.set_memory_budget(0);
+        assert!(grid.get(&ORIGIN).is_none());
+        for (coordinates, hexagon) in flat_hexagons(&area) {
+            grid.insert(&coordinates, hexagon);
+        }
+        assert_eq!(grid.visibility(&ORIGIN), Visibility::Explored);
+        assert_eq!(grid.visibility(&ORIGIN.shift(1, 0)), Visibility::Visible);
+        assert_eq!(grid.visibility(&ORIGIN.shift(2, 0)), Visibility::Unseen);
+    }
+
+    #[test]
+    fn rings_go_around_the_center() {
+        let center = Coordinates { q: 2, r: -5 };
+        assert_eq!(center.ring(0).collect::<Vec<_>>(), vec![center]);
+        assert_eq!(center.ring(-1).count(), 0);
+        for radius in 1..=5 {
+            let ring: Vec<Coordinates> = center.ring(radius).collect();
+            assert_eq!(center.ring(radius).size_hint(), (6 * radius as usize, Some(6 * radius as usize)));
+            assert_eq!(ring.len() as i32, 6 * radius);
+            assert_eq!(Area::ring(center, radius).len(), ring.len());
+            assert!(ring.iter().all(|coordinates| coordinates.distance_to(&center) == radius));
+            assert!(ring.windows(2).all(|pair| pair[0].distance_to(&pair[1]) == 1));
+        }
+    }
+
+    #[test]
+    fn spirals_cover_the_hexagon() {
+        let center = Coordinates { q: 2, r: -5 };
+        assert_eq!(center.spiral(0).collect::<Vec<_>>(), vec![center]);
+        assert_eq!(center.spiral(-2).count(), 0);
+        for radius in 0..=6 {
+            let spiral = Area::spiral(center, radius);
+            let hexagon = Area::hexagon(center, radius);
+            assert_eq!(center.spiral(radius).count() as i32, 3 * radius * (radius + 1) + 1);
+            assert_eq!(spiral.len(), hexagon.len());
+            assert!(hexagon.iter().all(|coordinates| spiral.contains(coordinates)));
+        }
+    }
+
+    #[test]
+    fn hexagons_at_the_edge_match_across_areas() {
+        let noise_graph = NoiseGraph::load("config/noise.ron").expect("Could not load the noise graph");
+        let biome_rules = BiomeRules::load("config/biomes.ron").expect("Could not load the biome rules");
+        let parameters = GeneratorParameters { seed: 0, humidity_scale: 0.97, humidity_bias: 0.1, world_scale: 16. };
+        let noise_generator = NoiseGenerator::new(&noise_graph, &parameters).expect("Could not build the noise generator");
+        let first_area = Area::hexagon(ORIGIN, 6);
+        let second_area = Area::hexagon(Coordinates { q: 10, r: -2 }, 6);
+        let first = Grid::generate_hexagons(&noise_generator, &biome_rules, &first_area);
+        let second = Grid::generate_hexagons(&noise_generator, &biome_rules, &second_area);
+
+        // The overlap lies on the edge of both areas, where the neighbors come from outside of the area
+        let overlap = first_area.intersection(&second_area);
+        assert!(!overlap.is_empty());
+        for coordinates in overlap.iter() {
+            assert_eq!(first[coordinates].texture_type, second[coordinates].texture_type, "{:?}", coordinates);
+            assert_eq!(first[coordinates].height, second[coordinates].height);
+        }
+    }
+
+    #[test]
+    fn parses_coordinates() {
+        assert_eq!("-3,4".parse::<Coordinates>(), Ok(Coordinates { q: -3, r: 4 }));
+        assert_eq!(" 1 , 2 ".parse::<Coordinates>(), Ok(Coordinates { q: 1, r: 2 }));
+        assert_eq!("1,2,3".parse::<Coordinates>(), Err("Invalid coordinates 1,2,3: expected q,r".to_string()));
+        assert_eq!("1".parse::<Coordinates>(), Err("Invalid coordinates 1: expected q,r".to_string()));
+        assert!("1,a".parse::<Coordinates>().is_err());
+
+        let coordinates = Coordinates { q: -12, r: 7 };
+        assert_eq!(coordinates.to_string(), "-12,7");
+        assert_eq!(coordinates.to_string().parse::<Coordinates>(), Ok(coordinates));
+    }
+
+    #[test]
+    fn seeded_hashes_are_pinned() {
+        // Changing these values changes every world generated so far
+        assert_eq!(ORIGIN.seeded_hash(0, 0), 0x2130_748A_AAC8_0268);
+        assert_eq!(Coordinates { q: 3, r: -5 }.seeded_hash(42, 0), 0x631E_8DEA_EED3_9642);
+        assert_eq!(Coordinates { q: 3, r: -5 }.seeded_hash(42, 1), 0x1D68_6272_9FBF_4C67);
+        assert_eq!(Coordinates { q: -100, r: 250 }.seeded_hash(7, 0xDEAD), 0x1D83_8E8F_A8F0_FD01);
+    }
+
+    #[test]
+    fn seeded_hashes_depend_on_the_seed_and_the_salt() {
+        for coordinates in Area::hexagon(ORIGIN, 3).iter() {
+            let hash = coordinates.seeded_hash(42, 0);
+            assert_ne!(hash, coordinates.seeded_hash(43, 0));
+            assert_ne!(hash, coordinates.seeded_hash(42, 1));
+        }
+    }
+
+    #[test]
+    fn draws_every_level_from_the_sea_floor() {
+        let area = Area::hexagon(ORIGIN, 1);
+        let (trench, slope, hill) = (ORIGIN, Coordinates { q: 1, r: 0 }, Coordinates { q: -1, r: 0 });
+        let grid = Grid::from_hexagons([(trench, -2.), (slope, -1.), (hill, 1.)].iter()
+            .map(|(coordinates, height)| (*coordinates, Hexagon::new((TerrainType::Flat, BiomeType::WDeep), *height))));
+        assert_eq!([trench, slope, hill].map(|coordinates| grid.get(&coordinates).map(|hexagon| hexagon.level)), [Some(-2), Some(-1), Some(1)]);
+
+        let mut renderer = RecordingRenderer::new();
+        grid.draw(&mut renderer, ORIGIN, &area);
+        let tiles_at = |level: i32| renderer.calls.iter()
+            .filter(|call| matches!(call, DrawCall::Tile { level: tile_level, .. } if *tile_level == level))
+            .count();
+        assert_eq!([-2, -1, 0, 1].map(tiles_at), [3, 2, 1, 1]);
+        let shadows: Vec<DrawCall> = renderer.calls.iter()
+            .filter(|call| matches!(call, DrawCall::Shadow { .. }))
+            .cloned()
+            .collect();
+        assert_eq!(shadows, vec![DrawCall::Shadow { coordinates: slope, level: -1 }, DrawCall::Shadow { coordinates: hill, level: 1 }]);
+    }
+
+    #[test]
+    fn keeps_the_current_area_whatever_the_budget() {
+        let noise_graph = NoiseGraph::load("config/noise.ron").expect("Could not load the noise graph");
+        let biome_rules = BiomeRules::load("config/biomes.ron").expect("Could not load the biome rules");
+        let parameters = GeneratorParameters { seed: 0, humidity_scale: 0.97, humidity_bias: 0.1, world_scale: 16. };
+        let noise_generator = NoiseGenerator::new(&noise_graph, &parameters).expect("Could not build the noise generator");
+        let area = Area::hexagon(ORIGIN, 10);
+        let mut grid = Grid::new(&noise_generator, &biome_rules, &area).expect("Could not build the grid");
+
+        grid.set_memory_budget(0);
+        assert!(area.iter().all(|coordinates| grid.get(coordinates).is_some()));
+
+        // Moving away drops the previous area
+        let far_away = Area::hexagon(Coordinates { q: 10 * Chunk::SIZE, r: 0 }, 2);
+        grid.at(&noise_generator, &biome_rules, &far_away);
+        assert!(grid.get(&ORIGIN).is_none());
+        assert!(far_away.iter().all(|coordinates| grid.get(coordinates).is_some()));
     }
 }
@@ -0,0 +1,113 @@
+use std::{thread, time};
+use std::error::Error;
+use std::sync::Arc;
+
+use sdl2::event::Event;
+use sdl2::image::SaveSurface;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+
+use biomes::BiomeRules;
+use explorer::{Action, Explorer};
+use generator::GeneratorParameters;
+use noise_graph::NoiseGraph;
+use renderer;
+use renderer::Printer;
+use textures::Textures;
+use workers::ChunkWorkers;
+use tiles::Coordinates;
+use ::{open_explorer, ExplorerSettings, WorldFiles, BIOME_RULES_PATH, NOISE_GRAPH_PATH, PIXEL_PER_HEXAGON};
+
+const LOGICAL_SCREEN_WIDTH: u32 = 1792;
+const LOGICAL_SCREEN_HEIGHT: u32 = 1120;
+const ORIGIN: (i32, i32) = ((LOGICAL_SCREEN_WIDTH / 2) as i32, (LOGICAL_SCREEN_HEIGHT / 2) as i32);
+
+pub fn run(full_screen: bool, width: u32, height: u32, parameters: GeneratorParameters, center_coordinates: Coordinates,
+           settings: &ExplorerSettings, files: &WorldFiles) -> Result<(), Box<dyn Error>> {
+    let biome_rules = Arc::new(BiomeRules::load(BIOME_RULES_PATH)?);
+    let noise_graph = Arc::new(NoiseGraph::load(NOISE_GRAPH_PATH)?);
+
+    let sdl_context = sdl2::init()?;
+    let video_subsys = sdl_context.video()?;
+    let (display_width, display_height) = video_subsys.display_bounds(0)?.size();
+    println!("display {}x{}", display_width, display_height);
+
+    let mut canvas;
+    {
+        let mut builder = video_subsys.window("Auto dungeon", width, height);
+        if full_screen { builder.fullscreen_desktop(); } else { builder.borderless(); }
+
+        canvas = builder.opengl()
+            .build()?
+            .into_canvas()
+            .build()?;
+    }
+
+    // TODO Apparently scales the whole screen each time which is highly ineffective, see:
+    // https://stackoverflow.com/questions/11043969/how-to-scale-to-resolution-in-sdl
+    canvas.set_logical_size(LOGICAL_SCREEN_WIDTH, LOGICAL_SCREEN_HEIGHT)
+        .map_err(|e| e.to_string())?;
+
+    let texture_creator = canvas.texture_creator();
+    let textures = Textures::new(&texture_creator, parameters.seed);
+
+    const GRID_RADIUS: i32 = 25;
+
+    let mut explorer = open_explorer(&noise_graph, &biome_rules, parameters, center_coordinates, GRID_RADIUS, files)?;
+    settings.apply(&mut explorer);
+    // Scrolls and parameter changes no longer wait for the generation, new hexagons show up as they arrive
+    explorer.set_workers(ChunkWorkers::new(Arc::clone(&noise_graph), Arc::clone(&biome_rules), ChunkWorkers::default_worker_count()));
+    explorer.set_radius(GRID_RADIUS);
+
+    let mut printer = Printer::new(&mut canvas, textures, ORIGIN, PIXEL_PER_HEXAGON);
+    explorer.draw(&mut printer);
+
+    let mut pristine = true;
+    let mut events = sdl_context.event_pump()?;
+    'main: loop {
+        for event in events.poll_iter() {
+            match event {
+                Event::Quit { .. } => break 'main,
+                Event::KeyDown { keycode: Option::Some(keycode), .. } => match renderer::key(keycode).and_then(Action::from_key) {
+                    None => {}
+                    Some(Action::Quit) => break 'main,
+                    Some(action) => {
+                        explorer.apply(action)?;
+                        pristine = false;
+                    }
+                },
+                // Mouse positions are scaled to the logical size of the canvas by SDL
+                Event::MouseButtonDown { x, y, .. } => {
+                    explorer.select((x - ORIGIN.0, y - ORIGIN.1));
+                    pristine = false;
+                }
+                _ => {}
+            }
+        }
+        if explorer.receive() {
+            pristine = false;
+        }
+        if !pristine {
+            explorer.draw(&mut printer);
+            pristine = true;
+        }
+        thread::sleep(time::Duration::from_millis(1024 / 32));
+    }
+
+    files.save(&explorer)?;
+    Ok(())
+}
+
+/// Draws the explored area with the textures of the viewer into a PNG file
+pub fn render_textured(explorer: &Explorer, width: u32, height: u32, output: &str) -> Result<(), Box<dyn Error>> {
+    let mut canvas = Surface::new(width, height, PixelFormatEnum::ABGR8888)?
+        .into_canvas()?;
+    let texture_creator = canvas.texture_creator();
+    let textures = Textures::new(&texture_creator, explorer.world().parameters.seed);
+    {
+        let mut printer = Printer::new(&mut canvas, textures, ((width / 2) as i32, (height / 2) as i32), PIXEL_PER_HEXAGON);
+        explorer.draw(&mut printer);
+    }
+    canvas.into_surface().save(output)?;
+    Ok(())
+}
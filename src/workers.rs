@@ -0,0 +1,150 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::thread::JoinHandle;
+
+use area::Area;
+use biomes::BiomeRules;
+use chunks::ChunkCoordinates;
+use generator::{GeneratorParameters, NoiseGenerator};
+use noise_graph::NoiseGraph;
+use tiles::{Coordinates, Grid, Hexagon};
+
+struct ChunkJob {
+    generation: u64,
+    parameters: GeneratorParameters,
+    chunk_coordinates: ChunkCoordinates,
+    missing: Area,
+}
+
+struct GeneratedChunk {
+    generation: u64,
+    chunk_coordinates: ChunkCoordinates,
+    hexagons: HashMap<Coordinates, Hexagon>,
+}
+
+/// Pool of threads generating chunks in the background.
+///
+/// Noise functions cannot be shared between threads, so each worker builds its own `NoiseGenerator` from the graph,
+/// again whenever the parameters change. Chunks requested before the last reset are skipped by the workers,
+/// or dropped if they were already being generated.
+pub struct ChunkWorkers {
+    jobs: Option<Sender<ChunkJob>>,
+    results: Receiver<GeneratedChunk>,
+    handles: Vec<JoinHandle<()>>,
+    pending: HashSet<ChunkCoordinates>,
+    /// Incremented on each reset, shared with the workers
+    generation: Arc<AtomicU64>,
+}
+
+impl ChunkWorkers {
+    pub fn new(noise_graph: Arc<NoiseGraph>, biome_rules: Arc<BiomeRules>, worker_count: usize) -> ChunkWorkers {
+        let (jobs, job_receiver) = channel::<ChunkJob>();
+        let (result_sender, results) = channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let generation = Arc::new(AtomicU64::new(0));
+
+        let handles = (0..worker_count.max(1))
+            .map(|_| {
+                let noise_graph = Arc::clone(&noise_graph);
+                let biome_rules = Arc::clone(&biome_rules);
+                let job_receiver = Arc::clone(&job_receiver);
+                let result_sender = result_sender.clone();
+                let current_generation = Arc::clone(&generation);
+                thread::spawn(move || {
+                    let mut generator: Option<(GeneratorParameters, NoiseGenerator)> = None;
+                    loop {
+                        // The lock is released as soon as a job is received, while it is being generated.
+                        // A worker stops once the queue is closed, or poisoned by another worker panicking.
+                        let job = match job_receiver.lock().map(|receiver| receiver.recv()) {
+                            Ok(Ok(job)) => job,
+                            _ => break,
+                        };
+                        if job.generation != current_generation.load(Ordering::Relaxed) {
+                            continue;
+                        }
+                        if generator.as_ref().is_none_or(|(parameters, _)| *parameters != job.parameters) {
+                            match NoiseGenerator::new(&noise_graph, &job.parameters) {
+                                Ok(noise_generator) => generator = Some((job.parameters, noise_generator)),
+                                Err(_) => break,
+                            }
+                        }
+                        let (_, noise_generator) = generator.as_ref().expect("Noise generator built above");
+                        let hexagons = Grid::generate_hexagons(noise_generator, &biome_rules, &job.missing);
+                        let generated = GeneratedChunk { generation: job.generation, chunk_coordinates: job.chunk_coordinates, hexagons };
+                        if result_sender.send(generated).is_err() {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        ChunkWorkers { jobs: Some(jobs), results, handles, pending: HashSet::new(), generation }
+    }
+
+    /// One worker per core, the main thread keeps one for the events and the drawing
+    pub fn default_worker_count() -> usize {
+        thread::available_parallelism().map_or(1, |count| count.get().saturating_sub(1).max(1))
+    }
+
+    /// Queues the generation of the chunks of the area missing from the grid, unless they are already queued.
+    /// Fails if the workers stopped, the chunks must then be generated without them.
+    pub fn request(&mut self, parameters: &GeneratorParameters, grid: &Grid, area: &Area) -> Result<(), String> {
+        let jobs = self.jobs.as_ref().ok_or("Chunk workers are stopped")?;
+        let generation = self.generation.load(Ordering::Relaxed);
+        for (chunk_coordinates, missing) in grid.missing_chunks(area) {
+            if self.pending.insert(chunk_coordinates) {
+                jobs.send(ChunkJob { generation, parameters: *parameters, chunk_coordinates, missing })
+                    .map_err(|_| "Chunk workers stopped")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forgets the queued chunks, for instance when the parameters change
+    pub fn reset(&mut self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        self.pending.clear();
+    }
+
+    /// Inserts the chunks generated since the last call into the grid, returns whether any arrived.
+    /// Fails once a worker stopped, as the chunks it was generating would never arrive.
+    pub fn receive(&mut self, grid: &mut Grid) -> Result<bool, String> {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let mut received = false;
+        loop {
+            let generated = match self.results.try_recv() {
+                Ok(generated) => generated,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Err("Chunk workers stopped".to_string()),
+            };
+            if generated.generation != generation {
+                continue;
+            }
+            self.pending.remove(&generated.chunk_coordinates);
+            for (coordinates, hexagon) in generated.hexagons {
+                grid.insert(&coordinates, hexagon);
+            }
+            received = true;
+        }
+        // Workers only stop by themselves when something went wrong
+        if self.handles.iter().any(|handle| handle.is_finished()) {
+            return Err("A chunk worker stopped".to_string());
+        }
+        Ok(received)
+    }
+}
+
+impl Drop for ChunkWorkers {
+    fn drop(&mut self) {
+        // Queued jobs are skipped and closing the queue stops the workers once their current job is done
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        self.jobs.take();
+        for handle in self.handles.drain(..) {
+            handle.join().ok();
+        }
+    }
+}
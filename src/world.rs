@@ -0,0 +1,138 @@
+use std::fs;
+
+use ron;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use generator::GeneratorParameters;
+use terrain::{BiomeType, TerrainType};
+use tiles::{Coordinates, Grid, Hexagon};
+
+/// Generated hexagons with everything needed to show them again: the parameters they come from and the view.
+///
+/// Every stored hexagon is saved, so regions visited before are saved too, along with the hexagons seen under the fog of war.
+pub struct World {
+    pub parameters: GeneratorParameters,
+    pub center: Coordinates,
+    pub radius: i32,
+    pub grid: Grid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedHexagon {
+    coordinates: Coordinates,
+    terrain: TerrainType,
+    biome: BiomeType,
+    height: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedWorld {
+    version: u32,
+    parameters: GeneratorParameters,
+    center: Coordinates,
+    radius: i32,
+    hexagons: Vec<SavedHexagon>,
+    explored: Vec<Coordinates>,
+}
+
+impl World {
+    /// Bumped whenever the saved format changes
+    const FORMAT_VERSION: u32 = 2;
+
+    pub fn load(path: &str) -> Result<World, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read world {}: {}", path, e))?;
+        let saved: SavedWorld = ron::from_str(&content)
+            .map_err(|e| format!("Could not parse world {}: {}", path, e))?;
+        if saved.version != World::FORMAT_VERSION {
+            return Err(format!("World {} was saved in version {}, only version {} can be loaded",
+                               path, saved.version, World::FORMAT_VERSION));
+        }
+
+        let mut grid = Grid::from_hexagons(saved.hexagons.into_iter()
+            .map(|hexagon| (hexagon.coordinates, Hexagon::new((hexagon.terrain, hexagon.biome), hexagon.height))));
+        grid.explore(saved.explored);
+        Ok(World { parameters: saved.parameters, center: saved.center, radius: saved.radius, grid })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut hexagons: Vec<SavedHexagon> = self.grid.hexagons()
+            .map(|(coordinates, hexagon)| SavedHexagon {
+                coordinates,
+                terrain: hexagon.texture_type.0.clone(),
+                biome: hexagon.texture_type.1.clone(),
+                height: hexagon.height,
+            })
+            .collect();
+        // Saving twice the same world gives the same file
+        hexagons.sort_by_key(|hexagon| (hexagon.coordinates.r, hexagon.coordinates.q));
+        let mut explored: Vec<Coordinates> = self.grid.explored().cloned().collect();
+        explored.sort_by_key(|coordinates| (coordinates.r, coordinates.q));
+
+        let saved = SavedWorld { version: World::FORMAT_VERSION, parameters: self.parameters, center: self.center, radius: self.radius, hexagons, explored };
+        let content = ron::ser::to_string_pretty(&saved, PrettyConfig::new().depth_limit(2))
+            .map_err(|e| format!("Could not serialize world {}: {}", path, e))?;
+        fs::write(path, content)
+            .map_err(|e| format!("Could not write world {}: {}", path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use area::Area;
+    use generator::GeneratorParameters;
+    use terrain::{BiomeType, TerrainType};
+    use tiles::{Coordinates, Grid, Hexagon, Visibility};
+    use world::World;
+
+    #[test]
+    fn loads_what_was_saved() {
+        let center = Coordinates { q: 3, r: -2 };
+        let textures = [
+            (TerrainType::Flat, BiomeType::WDeep),
+            (TerrainType::Hill, BiomeType::Boreal),
+            (TerrainType::Mont, BiomeType::Snow),
+        ];
+        let hexagons = Area::hexagon(center, 3).into_iter()
+            .enumerate()
+            .map(|(index, coordinates)| (coordinates, Hexagon::new(textures[index % 3].clone(), index as f64 / 7. - 0.9)));
+        let mut world = World {
+            parameters: GeneratorParameters { seed: 42, humidity_scale: 0.8, humidity_bias: -0.1, world_scale: 12.5 },
+            center,
+            radius: 3,
+            grid: Grid::from_hexagons(hexagons),
+        };
+        world.grid.reveal(&center.shift(-2, 0), 2);
+        world.grid.reveal(&center, 1);
+
+        let path = env::temp_dir().join(format!("auto-dungeon-world-{}.ron", process::id()));
+        let path = path.to_str().expect("Temporary path is not valid unicode");
+        world.save(path).expect("Could not save the world");
+        let loaded = World::load(path);
+        fs::remove_file(path).expect("Could not remove the saved world");
+        let loaded = loaded.expect("Could not load the world");
+
+        assert_eq!(loaded.parameters, world.parameters);
+        assert_eq!(loaded.center, world.center);
+        assert_eq!(loaded.radius, world.radius);
+        assert_eq!(loaded.grid.hexagons().count(), world.grid.hexagons().count());
+        for (coordinates, hexagon) in world.grid.hexagons() {
+            let loaded_hexagon = loaded.grid.get(&coordinates).expect("Saved hexagon is missing");
+            assert_eq!(loaded_hexagon.texture_type, hexagon.texture_type);
+            assert_eq!(loaded_hexagon.height, hexagon.height);
+            assert_eq!(loaded_hexagon.level, hexagon.level);
+            // Nothing is visible until the explorer looks around again, but everything seen is remembered
+            let expected = match world.grid.visibility(&coordinates) {
+                Visibility::Unseen => Visibility::Unseen,
+                _ => Visibility::Explored,
+            };
+            assert_eq!(loaded.grid.visibility(&coordinates), expected);
+        }
+        assert!(world.grid.explored().count() > 1);
+    }
+}